@@ -2,18 +2,115 @@
 
 #[ink::contract]
 mod workshop {
+    #[cfg(not(test))]
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::hash::Blake2x256;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
     use ink::storage::Mapping;
 
+    pub type DepositReference = [u8; 32];
+    /// (threshold, bps): the fee for withdrawals of at least `threshold`.
+    pub type FeeTier = (Balance, u16);
+    /// (tag, account, amount, timestamp) of a recorded operation.
+    pub type AuditEntry = (u8, AccountId, Balance, Timestamp);
+
+    pub const AUDIT_DEPOSIT: u8 = 0;
+    pub const AUDIT_WITHDRAW: u8 = 1;
+
+    const VERSION: u16 = 1;
+    const MAX_BPS: u16 = 10_000;
+    const MILLIS_PER_DAY: Timestamp = 86_400_000;
+    const AUDIT_LOG_SIZE: usize = 32;
+    const MAX_BATCH_QUERY: usize = 100;
+
     #[ink(event)]
     pub struct Deposited {
+        from: AccountId,
+        sponsor: AccountId,
+        balance: Balance,
+        new_balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct DepositedWithMemo {
         from: AccountId,
         balance: Balance,
+        memo: [u8; 32],
     }
 
+    /// `new_balance` is what is left in the source paid out of: the account, or the escrow
+    /// or stream for their payouts.
     #[ink(event)]
     pub struct Withdrawn {
         to: AccountId,
         balance: Balance,
+        new_balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Transferred {
+        from: AccountId,
+        to: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Approval {
+        owner: AccountId,
+        spender: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        previous: AccountId,
+        new: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct EmergencyWithdrawn {
+        to: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AccountClosed {
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct BonusCredited {
+        account: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Referred {
+        referrer: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct InterestAccrued {
+        account: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        new_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct AutoPaused {
+        window_outflow: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Forfeited {
+        from: AccountId,
+        amount: Balance,
     }
 
     #[derive(PartialEq, Debug, scale::Encode, scale::Decode)]
@@ -23,19 +120,425 @@ mod workshop {
         InsufficientFunds,
         ExpectedWithdrawalAmountExceedsAccountBalance,
         WithdrawTransferFailed,
+        BalanceOverflow,
+        SelfTransfer,
+        NotOwner,
+        ContractPaused,
+        InsufficientAllowance,
+        DepositBelowMinimum,
+        InvalidFee,
+        FundsLocked,
+        ReentrancyDetected,
+        DailyLimitExceeded,
+        InvalidNewOwner,
+        NotPendingOwner,
+        BalanceCapExceeded,
+        InvalidPercentage,
+        StreamAlreadyExists,
+        NoStream,
+        NothingToClaim,
+        ZeroDeposit,
+        InvalidNonce,
+        BadSignature,
+        NotPaused,
+        ZeroWithdrawal,
+        AlreadyPaused,
+        SavingsCooldownActive,
+        AccountFrozen,
+        DuplicateReference,
+        Insolvent,
+        ShareMismatch,
+        WithdrawCooldownActive,
+        AccountLimitReached,
+        EscrowNotFound,
+        NotPayer,
+        EscrowAlreadyResolved,
+        NotEscrowParty,
+        EscrowDisputed,
+        NotArbiter,
+        NotDisputed,
+        EscrowNotExpired,
+        NotWhitelisted,
+        UntrustedDestination,
+        BelowExistentialDeposit,
+        TooManyAccounts,
+        NotOperator,
+        CodeUpgradeFailed,
+        NoMigrationPending,
+        ExceedsPreauthorization,
+        NoSurplus,
+        FundsStillHeld,
+        TokenNotSet,
+        TokenTransferFailed,
+        DepositTooLarge,
+        WithdrawalAlreadyPending,
+        NoPendingWithdrawal,
+        WithdrawalDelayActive,
+        RateLimited,
+    }
+
+    #[derive(Clone, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Config {
+        pub owner: AccountId,
+        pub paused: bool,
+        pub fee_bps: u16,
+        pub min_deposit: Balance,
+        pub max_balance: Balance,
+        pub daily_limit: Balance,
+        pub emit_events: bool,
+        pub fee_tiers: Vec<FeeTier>,
+        pub max_deposit_per_tx: Balance,
+        pub penalty_recipient: AccountId,
+    }
+
+    #[derive(Clone, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Stream {
+        pub total: Balance,
+        pub claimed: Balance,
+        pub start: Timestamp,
+        pub duration: Timestamp,
+    }
+
+    #[derive(Clone, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Escrow {
+        pub payer: AccountId,
+        pub payee: AccountId,
+        pub arbiter: AccountId,
+        pub amount: Balance,
+        pub released_amount: Balance,
+        pub released: bool,
+        pub disputed: bool,
+        pub deadline: Timestamp,
+    }
+
+    #[derive(Clone, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Stats {
+        pub total_deposited: Balance,
+        pub account_count: u32,
+        pub contract_balance: Balance,
+        pub paused: bool,
+        pub owner: AccountId,
+    }
+
+    /// Error type of the PSP22 standard, needed to decode token call results.
+    #[derive(Debug, PartialEq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Psp22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
     }
 
     #[ink(storage)]
     pub struct Workshop {
         balances: Mapping<AccountId, Balance>,
+        allowances: Mapping<(AccountId, AccountId), Balance>,
+        locks: Mapping<AccountId, Timestamp>,
+        block_locks: Mapping<AccountId, BlockNumber>,
+        withdrawn_today: Mapping<AccountId, (Timestamp, Balance)>,
+        streams: Mapping<AccountId, Stream>,
+        nonces: Mapping<AccountId, u64>,
+        last_update: Mapping<AccountId, BlockNumber>,
+        last_activity_block: Mapping<AccountId, BlockNumber>,
+        savings: Mapping<AccountId, Balance>,
+        last_savings_deposit: Mapping<AccountId, Timestamp>,
+        frozen: Mapping<AccountId, bool>,
+        last_accrued: Mapping<AccountId, Timestamp>,
+        refs: Mapping<(AccountId, DepositReference), ()>,
+        last_withdraw: Mapping<AccountId, Timestamp>,
+        escrows: Mapping<u64, Escrow>,
+        lifetime_deposited: Mapping<AccountId, Balance>,
+        whitelist: Mapping<AccountId, bool>,
+        account_index: Mapping<u32, AccountId>,
+        account_positions: Mapping<AccountId, u32>,
+        trusted: Mapping<(AccountId, AccountId), ()>,
+        strict_withdraw: Mapping<AccountId, bool>,
+        operators: Mapping<(AccountId, AccountId), bool>,
+        last_deposit: Mapping<AccountId, (Timestamp, Balance)>,
+        pending: Mapping<AccountId, (Balance, Timestamp)>,
+        withdrawal_delay: Timestamp,
+        penalty_recipient: Option<AccountId>,
+        preauth: Mapping<AccountId, Balance>,
+        labels: Mapping<AccountId, [u8; 32]>,
+        token_balances: Mapping<AccountId, Balance>,
+        total_deposited: Balance,
+        owner: AccountId,
+        pending_owner: Option<AccountId>,
+        paused: bool,
+        min_deposit: Balance,
+        fee_bps: u16,
+        locked: bool,
+        daily_limit: Balance,
+        max_balance: Balance,
+        max_deposit_per_tx: Balance,
+        schema_version: u16,
+        savings_cooldown: Timestamp,
+        reward_pool: Balance,
+        referral_bps: u16,
+        yield_pool: Balance,
+        rate_bps_per_day: u16,
+        promo_pool: Balance,
+        first_deposit_bonus: Balance,
+        withdraw_cooldown: Timestamp,
+        audit_enabled: bool,
+        audit_log: Vec<AuditEntry>,
+        account_count: u32,
+        max_accounts: u32,
+        emit_events: bool,
+        next_escrow_id: u64,
+        whitelist_enabled: bool,
+        existential_deposit: Balance,
+        fee_to_pool_bps: u16,
+        refund_window: Timestamp,
+        migration_pending: bool,
+        fee_tiers: Vec<FeeTier>,
+        deposit_count: u64,
+        withdraw_count: u64,
+        token: Option<AccountId>,
+        total_token_deposited: Balance,
+        outflow_window_limit: Balance,
+        window_outflow: (Timestamp, Balance),
+        max_ops_per_block: u32,
+        block_ops: (BlockNumber, u32),
     }
 
     impl Workshop {
         #[ink(constructor)]
         pub fn new() -> Self {
+            Self::new_with_min(0)
+        }
+
+        /// Lets a factory deploy on behalf of `owner` instead of owning the contract itself.
+        #[ink(constructor)]
+        pub fn new_with_owner(owner: AccountId) -> Self {
+            let mut contract = Self::new_with_min(0);
+            contract.owner = owner;
+            contract
+        }
+
+        /// Replicates the settings returned by another deployment's `config`. Traps on an
+        /// invalid fee schedule or on caps below the minimum deposit.
+        #[ink(constructor)]
+        pub fn new_from_config(config: Config) -> Self {
+            assert!(
+                config.fee_bps <= MAX_BPS && Self::valid_fee_tiers(&config.fee_tiers),
+                "invalid fee schedule"
+            );
+            assert!(
+                (config.max_balance == 0 || config.max_balance >= config.min_deposit)
+                    && (config.max_deposit_per_tx == 0
+                        || config.max_deposit_per_tx >= config.min_deposit),
+                "caps below minimum deposit"
+            );
+
+            let mut contract = Self::new_with_min(config.min_deposit);
+            contract.owner = config.owner;
+            contract.paused = config.paused;
+            contract.fee_bps = config.fee_bps;
+            contract.max_balance = config.max_balance;
+            contract.daily_limit = config.daily_limit;
+            contract.emit_events = config.emit_events;
+            contract.fee_tiers = config.fee_tiers;
+            contract.max_deposit_per_tx = config.max_deposit_per_tx;
+            contract.penalty_recipient =
+                (config.penalty_recipient != config.owner).then_some(config.penalty_recipient);
+            contract
+        }
+
+        #[ink(constructor)]
+        pub fn new_with_min(min_deposit: Balance) -> Self {
             Self {
                 balances: Mapping::default(),
+                allowances: Mapping::default(),
+                locks: Mapping::default(),
+                block_locks: Mapping::default(),
+                withdrawn_today: Mapping::default(),
+                streams: Mapping::default(),
+                nonces: Mapping::default(),
+                last_update: Mapping::default(),
+                last_activity_block: Mapping::default(),
+                savings: Mapping::default(),
+                last_savings_deposit: Mapping::default(),
+                frozen: Mapping::default(),
+                last_accrued: Mapping::default(),
+                refs: Mapping::default(),
+                last_withdraw: Mapping::default(),
+                escrows: Mapping::default(),
+                lifetime_deposited: Mapping::default(),
+                whitelist: Mapping::default(),
+                account_index: Mapping::default(),
+                account_positions: Mapping::default(),
+                trusted: Mapping::default(),
+                strict_withdraw: Mapping::default(),
+                operators: Mapping::default(),
+                last_deposit: Mapping::default(),
+                pending: Mapping::default(),
+                withdrawal_delay: 0,
+                penalty_recipient: None,
+                preauth: Mapping::default(),
+                labels: Mapping::default(),
+                token_balances: Mapping::default(),
+                total_deposited: 0,
+                owner: Self::env().caller(),
+                pending_owner: None,
+                paused: false,
+                min_deposit,
+                fee_bps: 0,
+                locked: false,
+                daily_limit: 0,
+                max_balance: 0,
+                max_deposit_per_tx: 0,
+                schema_version: VERSION,
+                savings_cooldown: 0,
+                reward_pool: 0,
+                referral_bps: 0,
+                yield_pool: 0,
+                rate_bps_per_day: 0,
+                promo_pool: 0,
+                first_deposit_bonus: 0,
+                withdraw_cooldown: 0,
+                audit_enabled: false,
+                audit_log: Vec::new(),
+                account_count: 0,
+                max_accounts: 0,
+                emit_events: true,
+                next_escrow_id: 0,
+                whitelist_enabled: false,
+                existential_deposit: 0,
+                fee_to_pool_bps: 0,
+                refund_window: 0,
+                migration_pending: false,
+                fee_tiers: Vec::new(),
+                deposit_count: 0,
+                withdraw_count: 0,
+                token: None,
+                total_token_deposited: 0,
+                outflow_window_limit: 0,
+                window_outflow: (0, 0),
+                max_ops_per_block: 0,
+                block_ops: (0, 0),
+            }
+        }
+
+        #[ink(message)]
+        pub fn token(&self) -> Option<AccountId> {
+            self.token
+        }
+
+        /// The token can only be switched once every account has withdrawn its tokens, as
+        /// their balances are denominated in the current one.
+        #[ink(message)]
+        pub fn set_token(&mut self, token: Option<AccountId>) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if token != self.token && self.total_token_deposited > 0 {
+                return Err(ContractError::FundsStillHeld);
+            }
+
+            self.token = token;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn total_token_deposited(&self) -> Balance {
+            self.total_token_deposited
+        }
+
+        #[ink(message)]
+        pub fn token_balance_of(&self, account: AccountId) -> Balance {
+            self.token_balances.get(account).unwrap_or(0)
+        }
+
+        /// Pulls `amount` of the configured PSP22 token from the caller, who must have
+        /// approved this contract beforehand.
+        #[ink(message)]
+        pub fn deposit_token(&mut self, amount: Balance) -> Result<Balance, ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+            self.ensure_not_frozen(caller)?;
+            let token = self.token.ok_or(ContractError::TokenNotSet)?;
+
+            if self.whitelist_enabled && !self.is_whitelisted(caller) {
+                return Err(ContractError::NotWhitelisted);
+            }
+
+            if amount == 0 {
+                return Err(ContractError::ZeroDeposit);
+            }
+
+            let token_balance: Balance = self.token_balance_of(caller);
+            let new_balance = token_balance
+                .checked_add(amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let total_token_deposited = self.total_token_deposited;
+            let new_total = total_token_deposited
+                .checked_add(amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+            self.count_block_op()?;
+
+            self.non_reentrant(|contract| {
+                contract.token_balances.insert(caller, &new_balance);
+                contract.total_token_deposited = new_total;
+
+                if let Err(error) = contract.pull_token(token, caller, amount) {
+                    contract.token_balances.insert(caller, &token_balance);
+                    contract.total_token_deposited = total_token_deposited;
+                    return Err(error);
+                }
+
+                Ok(new_balance)
+            })
+        }
+
+        #[ink(message)]
+        pub fn withdraw_token(&mut self, amount: Balance) -> Result<Balance, ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+            self.ensure_not_frozen(caller)?;
+            let token = self.token.ok_or(ContractError::TokenNotSet)?;
+            let token_balance: Balance = self.token_balance_of(caller);
+
+            if amount == 0 {
+                return Err(ContractError::ZeroWithdrawal);
+            }
+
+            if amount > token_balance {
+                return Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance);
             }
+
+            let total_token_deposited = self.total_token_deposited;
+            let new_total = total_token_deposited
+                .checked_sub(amount)
+                .ok_or(ContractError::Insolvent)?;
+            self.count_block_op()?;
+            self.non_reentrant(|contract| {
+                contract
+                    .token_balances
+                    .insert(caller, &(token_balance - amount));
+                contract.total_token_deposited = new_total;
+
+                if let Err(error) = contract.send_token(token, caller, amount) {
+                    contract.token_balances.insert(caller, &token_balance);
+                    contract.total_token_deposited = total_token_deposited;
+                    return Err(error);
+                }
+
+                Ok(token_balance - amount)
+            })
         }
 
         #[ink(message)]
@@ -48,120 +551,7846 @@ mod workshop {
             }
         }
 
+        #[ink(message)]
+        pub fn balance_of(&self, account: AccountId) -> Balance {
+            self.balances.get(account).unwrap_or(0)
+        }
+
+        /// Balances of `accounts` in the same order, 0 for unknown accounts.
+        #[ink(message)]
+        pub fn balances_of(&self, accounts: Vec<AccountId>) -> Result<Vec<Balance>, ContractError> {
+            if accounts.len() > MAX_BATCH_QUERY {
+                return Err(ContractError::TooManyAccounts);
+            }
+
+            Ok(accounts
+                .into_iter()
+                .map(|account| self.balance_of(account))
+                .collect())
+        }
+
+        #[ink(message)]
+        pub fn account_info(&self, account: AccountId) -> Option<(Balance, BlockNumber)> {
+            let balance: Balance = self.balances.get(account)?;
+
+            Some((balance, self.last_update.get(account).unwrap_or(0)))
+        }
+
+        /// Block of the account's last deposit, withdrawal or transfer, `None` if it never
+        /// had any. Unlike `account_info`, admin credits and interest don't count.
+        #[ink(message)]
+        pub fn last_activity_block_of(&self, account: AccountId) -> Option<BlockNumber> {
+            self.last_activity_block.get(account)
+        }
+
+        #[ink(message)]
+        pub fn has_account(&self, account: AccountId) -> bool {
+            self.balances.contains(account)
+        }
+
         #[ink(message, payable)]
-        pub fn deposit(&mut self) -> Result<(), ContractError> {
+        pub fn deposit(&mut self) -> Result<Balance, ContractError> {
             let caller = self.get_caller();
-            let transferred_funds: Balance = self.check_and_get_transferred_funds()?;
-            let account_balance: Balance = self.get_balance_by_account().unwrap_or(0);
+            self.credit_deposit(caller, self.emit_events)
+        }
+
+        /// Credits the caller like `deposit` without emitting any event.
+        #[ink(message, payable)]
+        pub fn deposit_quiet(&mut self) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            self.credit_deposit(caller, false)?;
 
-            let new_balance = account_balance + transferred_funds;
+            Ok(())
+        }
 
-            self.balances.insert(caller, &new_balance);
+        /// Credits the caller like `deposit` and emits `memo` for off-chain reconciliation.
+        /// The memo is not stored and is emitted even when `emit_events` is off.
+        #[ink(message, payable)]
+        pub fn deposit_with_memo(&mut self, memo: [u8; 32]) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            self.credit_deposit(caller, self.emit_events)?;
 
-            self.env().emit_event(Deposited {
+            self.env().emit_event(DepositedWithMemo {
                 from: caller,
-                balance: transferred_funds,
+                balance: self.env().transferred_value(),
+                memo,
             });
 
             Ok(())
         }
 
-        #[ink(message)]
-        pub fn withdraw(
-            &mut self,
-            withdrawal_amount: Option<Balance>,
-        ) -> Result<(), ContractError> {
+        #[ink(message, payable)]
+        pub fn deposit_for(&mut self, beneficiary: AccountId) -> Result<(), ContractError> {
+            self.credit_deposit(beneficiary, self.emit_events)?;
+
+            Ok(())
+        }
+
+        /// Credits the caller like `deposit` and pays `referrer` a `referral_bps` bonus out of
+        /// the reward pool. The bonus is skipped when the pool runs dry, on self-referral or
+        /// for frozen referrers.
+        #[ink(message, payable)]
+        pub fn deposit_with_referrer(&mut self, referrer: AccountId) -> Result<(), ContractError> {
             let caller = self.get_caller();
-            let mut account_balance: Balance = self.get_balance_by_account()?;
+            self.credit_deposit(caller, self.emit_events)?;
 
-            if account_balance == 0 {
-                return Err(ContractError::AccountWithoutBalance);
+            if referrer == caller || self.is_frozen(referrer) {
+                return Ok(());
             }
 
-            let withdrawal_amount: Balance = withdrawal_amount.unwrap_or(account_balance);
+            let bonus: Balance = self
+                .env()
+                .transferred_value()
+                .checked_mul(Balance::from(self.referral_bps))
+                .ok_or(ContractError::BalanceOverflow)?
+                / Balance::from(MAX_BPS);
+            let bonus = bonus.min(self.reward_pool);
 
-            if withdrawal_amount > account_balance {
-                return Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance);
+            if bonus == 0 {
+                return Ok(());
             }
 
-            account_balance -= withdrawal_amount;
-            self.balances.insert(caller, &account_balance);
+            self.accrue_interest(referrer)?;
+            let referrer_balance = self
+                .balance_of(referrer)
+                .checked_add(bonus)
+                .ok_or(ContractError::BalanceOverflow)?;
 
-            if self.env().transfer(caller, withdrawal_amount).is_err() {
-                return Err(ContractError::WithdrawTransferFailed);
-            }
+            self.set_balance(referrer, referrer_balance);
+            self.reward_pool -= bonus;
+            self.record_update(referrer);
 
-            self.env().emit_event(Withdrawn {
-                to: caller,
-                balance: withdrawal_amount,
+            self.env().emit_event(Referred {
+                referrer,
+                amount: bonus,
             });
 
             Ok(())
         }
 
-        fn get_caller(&self) -> AccountId {
-            self.env().caller()
+        #[ink(message, payable)]
+        pub fn deposit_split(
+            &mut self,
+            shares: Vec<(AccountId, Balance)>,
+        ) -> Result<(), ContractError> {
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(self.get_caller())?;
+            let transferred_funds: Balance = self.check_and_get_deposited_funds()?;
+            let total: Balance = shares
+                .iter()
+                .try_fold(0, |total: Balance, (_, amount)| total.checked_add(*amount))
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            if total != transferred_funds {
+                return Err(ContractError::ShareMismatch);
+            }
+
+            for (beneficiary, amount) in shares.iter() {
+                if *amount == 0 {
+                    return Err(ContractError::ZeroDeposit);
+                }
+                self.ensure_not_frozen(*beneficiary)?;
+                self.credit(*beneficiary, *amount)?;
+            }
+
+            Ok(())
         }
 
-        fn check_and_get_transferred_funds(&self) -> Result<Balance, ContractError> {
-            let transferred_funds: Balance = self.env().transferred_value();
-            if transferred_funds == 0 {
-                return Err(ContractError::InsufficientFunds);
+        #[ink(message, payable)]
+        pub fn deposit_ref(&mut self, reference: DepositReference) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+
+            if self.refs.contains((caller, reference)) {
+                return Err(ContractError::DuplicateReference);
             }
 
-            Ok(transferred_funds)
+            self.credit_deposit(caller, self.emit_events)?;
+            self.refs.insert((caller, reference), &());
+
+            Ok(())
         }
-    }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+        #[ink(message, payable)]
+        pub fn deposit_locked(&mut self, unlock_at: Timestamp) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            self.credit_deposit(caller, self.emit_events)?;
 
-        fn get_default_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
-            ink::env::test::default_accounts::<ink::env::DefaultEnvironment>()
+            let current_lock: Timestamp = self.locks.get(caller).unwrap_or(0);
+            self.locks.insert(caller, &current_lock.max(unlock_at));
+
+            Ok(())
         }
 
-        fn init() -> (
-            Workshop,
-            ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>,
-        ) {
-            (Workshop::new(), get_default_accounts())
+        #[ink(message)]
+        pub fn unlock_time(&self) -> Option<Timestamp> {
+            self.locks.get(self.get_caller())
         }
 
-        fn set_caller(sender: AccountId) {
-            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(sender);
+        #[ink(message, payable)]
+        pub fn deposit_locked_until_block(
+            &mut self,
+            unlock_block: BlockNumber,
+        ) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            self.credit_deposit(caller, self.emit_events)?;
+
+            let current_lock: BlockNumber = self.block_locks.get(caller).unwrap_or(0);
+            self.block_locks
+                .insert(caller, &current_lock.max(unlock_block));
+
+            Ok(())
         }
 
-        #[ink::test]
-        fn withdraw_works() {
-            // Arrange
-            let (mut contract, accounts) = init();
-            let caller = accounts.bob;
-            let balance_amount = 1000;
-            let withdrawal_amount = 600;
-            contract.balances.insert(caller, &balance_amount);
-            set_caller(caller);
+        #[ink(message)]
+        pub fn unlock_block(&self) -> Option<BlockNumber> {
+            self.block_locks.get(self.get_caller())
+        }
 
-            // Act
-            contract.withdraw(Some(withdrawal_amount)).unwrap();
-            let result = contract.balances.get(caller).unwrap();
+        #[ink(message)]
+        pub fn withdraw(
+            &mut self,
+            withdrawal_amount: Option<Balance>,
+        ) -> Result<Balance, ContractError> {
+            let caller = self.get_caller();
+            let withdrawal_amount = match withdrawal_amount {
+                Some(withdrawal_amount) => withdrawal_amount,
+                None => self.accrued_balance_of(caller)?,
+            };
+            self.with_cooldown(caller, |contract| {
+                contract.non_reentrant(|contract| {
+                    contract.do_withdraw(caller, caller, withdrawal_amount)
+                })
+            })?;
 
-            // Assert
-            assert_eq!(result, balance_amount - withdrawal_amount);
+            Ok(self.balance_of(caller))
         }
 
-        #[ink::test]
-        fn withdraw_fails() {
-            // Arrange
-            let (mut contract, accounts) = init();
-            let caller = accounts.bob;
-            set_caller(caller);
+        /// Runs every check `withdraw(Some(amount))` would, without moving funds.
+        #[ink(message)]
+        pub fn can_withdraw(&self, amount: Balance) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            if self.cooldown_active(caller) {
+                return Err(ContractError::WithdrawCooldownActive);
+            }
 
-            // Act
-            let result = contract.withdraw(None);
+            self.validate_withdrawal(caller, caller, amount)
+        }
 
-            // Assert
-            assert_eq!(result, Err(ContractError::AccountWithoutBalance));
+        /// The most the caller could withdraw right now, pending interest included, given locks,
+        /// the cooldown, the daily limit and any preauthorization.
+        #[ink(message)]
+        pub fn available_to_withdraw(&self) -> Balance {
+            let caller = self.get_caller();
+            if self.paused
+                || self.is_frozen(caller)
+                || self.ensure_unlocked(caller).is_err()
+                || self.cooldown_active(caller)
+            {
+                return 0;
+            }
+
+            self.balance_of(caller)
+                .saturating_add(self.pending_interest(caller))
+                .min(self.remaining_daily_limit())
+                .min(self.preauth.get(caller).unwrap_or(Balance::MAX))
+        }
+
+        /// What the caller may still withdraw today under the daily limit; `Balance::MAX` when
+        /// there is no limit.
+        #[ink(message)]
+        pub fn remaining_daily_limit(&self) -> Balance {
+            if self.daily_limit == 0 {
+                return Balance::MAX;
+            }
+
+            self.daily_limit
+                .saturating_sub(self.withdrawn_today_of(self.get_caller()))
+        }
+
+        /// `(net_amount, fee)` the caller would get for withdrawing `amount` under the current
+        /// fee schedule. Fails like `withdraw` would when the fee cannot be computed.
+        #[ink(message)]
+        pub fn preview_withdraw(
+            &self,
+            amount: Balance,
+        ) -> Result<(Balance, Balance), ContractError> {
+            let fee = self.withdrawal_fee(self.get_caller(), amount)?;
+
+            Ok((amount - fee, fee))
+        }
+
+        /// Like `withdraw`, but refuses payouts that would arrive below `existential_deposit`
+        /// and risk the transfer being rejected or the recipient being reaped.
+        #[ink(message)]
+        pub fn withdraw_keep_alive(
+            &mut self,
+            withdrawal_amount: Option<Balance>,
+        ) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            let withdrawal_amount = match withdrawal_amount {
+                Some(withdrawal_amount) => withdrawal_amount,
+                None => self.accrued_balance_of(caller)?,
+            };
+            let fee: Balance = self.withdrawal_fee(caller, withdrawal_amount)?;
+
+            if withdrawal_amount - fee < self.existential_deposit {
+                return Err(ContractError::BelowExistentialDeposit);
+            }
+
+            self.with_cooldown(caller, |contract| {
+                contract.non_reentrant(|contract| {
+                    contract.do_withdraw(caller, caller, withdrawal_amount)
+                })
+            })
+        }
+
+        #[ink(message)]
+        pub fn existential_deposit(&self) -> Balance {
+            self.existential_deposit
+        }
+
+        #[ink(message)]
+        pub fn set_existential_deposit(
+            &mut self,
+            existential_deposit: Balance,
+        ) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.existential_deposit = existential_deposit;
+
+            Ok(())
+        }
+
+        /// Caps the caller's future withdrawals to `amount` in total; every withdrawal draws
+        /// the ceiling down until it is raised again.
+        #[ink(message)]
+        pub fn preauthorize(&mut self, amount: Balance) -> Result<(), ContractError> {
+            self.count_block_op()?;
+            self.preauth.insert(self.get_caller(), &amount);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn preauthorization_of(&self, account: AccountId) -> Option<Balance> {
+            self.preauth.get(account)
+        }
+
+        /// Display name for the caller's own account.
+        #[ink(message)]
+        pub fn set_label(&mut self, label: [u8; 32]) -> Result<(), ContractError> {
+            self.count_block_op()?;
+            self.labels.insert(self.get_caller(), &label);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn label_of(&self, account: AccountId) -> Option<[u8; 32]> {
+            self.labels.get(account)
+        }
+
+        /// Lets `operator` withdraw the caller's funds, always paid to the caller.
+        #[ink(message)]
+        pub fn set_operator(
+            &mut self,
+            operator: AccountId,
+            approved: bool,
+        ) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            self.count_block_op()?;
+            if approved {
+                self.operators.insert((caller, operator), &true);
+            } else {
+                self.operators.remove((caller, operator));
+            }
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_operator(&self, owner: AccountId, operator: AccountId) -> bool {
+            self.operators.get((owner, operator)).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn withdraw_as_operator(
+            &mut self,
+            owner: AccountId,
+            withdrawal_amount: Balance,
+        ) -> Result<(), ContractError> {
+            if !self.is_operator(owner, self.get_caller()) {
+                return Err(ContractError::NotOperator);
+            }
+
+            self.with_cooldown(owner, |contract| {
+                contract
+                    .non_reentrant(|contract| contract.do_withdraw(owner, owner, withdrawal_amount))
+            })
+        }
+
+        /// Withdraws without fee while still inside `refund_window` of the caller's most
+        /// recent deposit and within its amount; otherwise behaves like `withdraw`.
+        #[ink(message)]
+        pub fn refund_recent(&mut self, amount: Balance) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            let refundable: Balance = match self.last_deposit.get(caller) {
+                Some((deposited_at, deposited))
+                    if self.env().block_timestamp()
+                        < deposited_at.saturating_add(self.refund_window) =>
+                {
+                    deposited
+                }
+                _ => 0,
+            };
+            let fee_free = amount <= refundable;
+
+            self.with_cooldown(caller, |contract| {
+                contract.non_reentrant(|contract| {
+                    contract.do_withdraw_with_fee(caller, caller, amount, !fee_free, true)
+                })
+            })?;
+
+            if fee_free {
+                if let Some((deposited_at, deposited)) = self.last_deposit.get(caller) {
+                    self.last_deposit
+                        .insert(caller, &(deposited_at, deposited - amount));
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Debits `amount` now and queues it for `execute_withdrawal` once the withdrawal delay
+        /// has passed. Only one request per account can be pending.
+        #[ink(message)]
+        pub fn request_withdrawal(&mut self, amount: Balance) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+
+            if self.pending.contains(caller) {
+                return Err(ContractError::WithdrawalAlreadyPending);
+            }
+
+            self.with_cooldown(caller, |contract| {
+                contract.non_reentrant(|contract| {
+                    contract.validate_withdrawal(caller, caller, amount)?;
+                    contract.count_block_op()?;
+                    contract.accrue_interest(caller)?;
+
+                    let today = contract.env().block_timestamp() / MILLIS_PER_DAY;
+                    let withdrawn_today = contract.withdrawn_today_of(caller) + amount;
+                    contract
+                        .withdrawn_today
+                        .insert(caller, &(today, withdrawn_today));
+                    if let Some(preauthorized) = contract.preauth.get(caller) {
+                        contract.preauth.insert(caller, &(preauthorized - amount));
+                    }
+
+                    contract.set_balance(caller, contract.balance_of(caller) - amount);
+                    contract
+                        .pending
+                        .insert(caller, &(amount, contract.env().block_timestamp()));
+                    contract.record_update(caller);
+                    contract.record_activity(caller);
+
+                    Ok(())
+                })
+            })
+        }
+
+        /// Pays out the queued withdrawal of `account`, minus the usual fee. Anyone may call
+        /// this once the withdrawal delay has passed.
+        #[ink(message)]
+        pub fn execute_withdrawal(&mut self, account: AccountId) -> Result<Balance, ContractError> {
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(account)?;
+            let (amount, requested_at) = self
+                .pending
+                .get(account)
+                .ok_or(ContractError::NoPendingWithdrawal)?;
+
+            if self.env().block_timestamp() < requested_at.saturating_add(self.withdrawal_delay) {
+                return Err(ContractError::WithdrawalDelayActive);
+            }
+
+            self.count_block_op()?;
+            let fee: Balance = self.withdrawal_fee(account, amount)?;
+            let pool_fee: Balance = self.pool_share(fee)?;
+            let net_amount: Balance = amount - fee;
+
+            self.pending.remove(account);
+            if let Err(error) = self.non_reentrant(|contract| contract.pay_out(account, net_amount))
+            {
+                self.pending.insert(account, &(amount, requested_at));
+                return Err(error);
+            }
+
+            if fee > 0 {
+                self.accrue_interest(self.owner)?;
+                let owner_balance: Balance = self.balance_of(self.owner);
+                self.set_balance(self.owner, owner_balance.saturating_add(fee - pool_fee));
+                self.reward_pool = self.reward_pool.saturating_add(pool_fee);
+            }
+            self.withdraw_count = self.withdraw_count.saturating_add(1);
+            self.record_activity(account);
+
+            if self.emit_events {
+                self.env().emit_event(Withdrawn {
+                    to: account,
+                    balance: net_amount,
+                    new_balance: self.balance_of(account),
+                });
+            }
+            self.audit(AUDIT_WITHDRAW, account, net_amount);
+
+            Ok(net_amount)
+        }
+
+        /// Drops the caller's queued withdrawal and credits the amount back, along with the
+        /// preauthorization and, on the day it was requested, the daily limit it used up.
+        #[ink(message)]
+        pub fn cancel_withdrawal(&mut self) -> Result<Balance, ContractError> {
+            let caller = self.get_caller();
+            self.count_block_op()?;
+            let (amount, requested_at) = self
+                .pending
+                .take(caller)
+                .ok_or(ContractError::NoPendingWithdrawal)?;
+
+            self.accrue_interest(caller)?;
+            let new_balance = self
+                .balance_of(caller)
+                .checked_add(amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+            self.set_balance(caller, new_balance);
+            self.record_update(caller);
+
+            if let Some((day, withdrawn)) = self.withdrawn_today.get(caller) {
+                if day == requested_at / MILLIS_PER_DAY {
+                    self.withdrawn_today
+                        .insert(caller, &(day, withdrawn.saturating_sub(amount)));
+                }
+            }
+            if let Some(preauthorized) = self.preauth.get(caller) {
+                self.preauth
+                    .insert(caller, &preauthorized.saturating_add(amount));
+            }
+
+            Ok(new_balance)
+        }
+
+        #[ink(message)]
+        pub fn pending_withdrawal_of(&self, account: AccountId) -> Option<(Balance, Timestamp)> {
+            self.pending.get(account)
+        }
+
+        #[ink(message)]
+        pub fn withdrawal_delay(&self) -> Timestamp {
+            self.withdrawal_delay
+        }
+
+        #[ink(message)]
+        pub fn set_withdrawal_delay(&mut self, delay: Timestamp) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.withdrawal_delay = delay;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn refund_window(&self) -> Timestamp {
+            self.refund_window
+        }
+
+        /// Milliseconds after a deposit during which `refund_recent` skips the fee.
+        #[ink(message)]
+        pub fn set_refund_window(&mut self, refund_window: Timestamp) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.refund_window = refund_window;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn withdraw_to(
+            &mut self,
+            to: AccountId,
+            withdrawal_amount: Option<Balance>,
+        ) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            let withdrawal_amount = match withdrawal_amount {
+                Some(withdrawal_amount) => withdrawal_amount,
+                None => self.accrued_balance_of(caller)?,
+            };
+            self.with_cooldown(caller, |contract| {
+                contract
+                    .non_reentrant(|contract| contract.do_withdraw(caller, to, withdrawal_amount))
+            })
+        }
+
+        #[ink(message)]
+        pub fn withdraw_percent(&mut self, percent: u8) -> Result<Balance, ContractError> {
+            if percent > 100 {
+                return Err(ContractError::InvalidPercentage);
+            }
+
+            let caller = self.get_caller();
+            let account_balance: Balance = self.accrued_balance_of(caller)?;
+            let percent = Balance::from(percent);
+            // Split to keep the multiplication from overflowing on large balances.
+            let withdrawal_amount =
+                account_balance / 100 * percent + account_balance % 100 * percent / 100;
+            self.with_cooldown(caller, |contract| {
+                contract.non_reentrant(|contract| {
+                    contract.do_withdraw(caller, caller, withdrawal_amount)
+                })
+            })?;
+
+            Ok(withdrawal_amount)
+        }
+
+        #[ink(message)]
+        pub fn multi_withdraw(
+            &mut self,
+            payouts: Vec<(AccountId, Balance)>,
+        ) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            self.accrue_interest(caller)?;
+            let total: Balance = payouts
+                .iter()
+                .try_fold(0, |total: Balance, (_, amount)| total.checked_add(*amount))
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            if total == 0 {
+                return Err(ContractError::ZeroWithdrawal);
+            }
+
+            if total > self.balance_of(caller) {
+                return Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance);
+            }
+
+            self.with_cooldown(caller, |contract| {
+                contract.non_reentrant(|contract| {
+                    let owner = contract.owner;
+                    contract.accrue_interest(owner)?;
+                    let caller_balance: Balance = contract.balance_of(caller);
+                    let owner_balance: Balance = contract.balance_of(owner);
+                    let total_deposited: Balance = contract.total_deposited;
+                    let reward_pool: Balance = contract.reward_pool;
+                    let withdrawn_today = contract.withdrawn_today.get(caller);
+                    let preauthorized = contract.preauth.get(caller);
+                    let window_outflow = contract.window_outflow;
+                    let paused = contract.paused;
+
+                    for (to, amount) in payouts.iter() {
+                        if let Err(error) = contract.do_withdraw(caller, *to, *amount) {
+                            // Undo the payouts already debited so the batch is all-or-nothing.
+                            contract.set_balance(caller, caller_balance);
+                            contract.set_balance(owner, owner_balance);
+                            contract.total_deposited = total_deposited;
+                            contract.reward_pool = reward_pool;
+                            if let Some(preauthorized) = preauthorized {
+                                contract.preauth.insert(caller, &preauthorized);
+                            }
+                            match withdrawn_today {
+                                Some(withdrawn_today) => {
+                                    contract.withdrawn_today.insert(caller, &withdrawn_today);
+                                }
+                                None => contract.withdrawn_today.remove(caller),
+                            }
+                            contract.window_outflow = window_outflow;
+                            contract.paused = paused;
+                            return Err(error);
+                        }
+                    }
+
+                    Ok(())
+                })
+            })
+        }
+
+        #[ink(message)]
+        pub fn owner_withdraw(&mut self, to: AccountId) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+            let owner = self.owner;
+            let owner_balance: Balance = self.accrued_balance_of(owner)?;
+            self.non_reentrant(|contract| contract.do_withdraw(owner, to, owner_balance))?;
+
+            Ok(owner_balance)
+        }
+
+        /// Lets a relayer submit a withdrawal authorized off-chain by `owner`. The 65-byte
+        /// recoverable ECDSA signature must cover the blake2x256 hash of the SCALE-encoded
+        /// `(contract, owner, amount, nonce)` and recover to the public key behind `owner`.
+        /// Including this contract's address keeps it from being replayed on other deployments.
+        #[ink(message)]
+        pub fn withdraw_signed(
+            &mut self,
+            owner: AccountId,
+            amount: Balance,
+            nonce: u64,
+            signature: [u8; 65],
+        ) -> Result<(), ContractError> {
+            if nonce != self.nonce_of(owner) {
+                return Err(ContractError::InvalidNonce);
+            }
+
+            let message_hash = self.env().hash_encoded::<Blake2x256, _>(&(
+                self.env().account_id(),
+                owner,
+                amount,
+                nonce,
+            ));
+            self.ensure_signed_by(owner, &message_hash, &signature)?;
+
+            self.with_cooldown(owner, |contract| {
+                contract.non_reentrant(|contract| contract.do_withdraw(owner, owner, amount))
+            })?;
+            self.nonces.insert(owner, &(nonce + 1));
+
+            Ok(())
+        }
+
+        /// One-shot internal transfer authorized off-chain by `from`, signed like
+        /// `withdraw_signed` over `(contract, from, to, amount, nonce)` and sharing its nonce.
+        #[ink(message)]
+        pub fn authorized_transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+            nonce: u64,
+            signature: [u8; 65],
+        ) -> Result<(), ContractError> {
+            self.ensure_not_paused()?;
+
+            if nonce != self.nonce_of(from) {
+                return Err(ContractError::InvalidNonce);
+            }
+
+            let message_hash = self.env().hash_encoded::<Blake2x256, _>(&(
+                self.env().account_id(),
+                from,
+                to,
+                amount,
+                nonce,
+            ));
+            self.ensure_signed_by(from, &message_hash, &signature)?;
+
+            self.move_balance(from, to, amount)?;
+            self.nonces.insert(from, &(nonce + 1));
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn nonce_of(&self, account: AccountId) -> u64 {
+            self.nonces.get(account).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn force_refund(&mut self, account: AccountId) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+            let account_balance: Balance = self.accrued_balance_of(account)?;
+            self.non_reentrant(|contract| {
+                contract.do_withdraw_with_fee(account, account, account_balance, false, false)
+            })?;
+
+            Ok(account_balance)
+        }
+
+        /// Gives up `amount` (the whole balance when `None`) to the treasury, which is the
+        /// penalty recipient's internal balance. No funds leave the contract.
+        #[ink(message)]
+        pub fn forfeit(&mut self, amount: Option<Balance>) -> Result<Balance, ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+            let recipient = self.penalty_recipient();
+            self.ensure_not_frozen(caller)?;
+
+            if caller == recipient {
+                return Err(ContractError::SelfTransfer);
+            }
+
+            self.accrue_interest(caller)?;
+            let account_balance: Balance = self
+                .balances
+                .get(caller)
+                .ok_or(ContractError::AccountWithoutBalance)?;
+            let amount = amount.unwrap_or(account_balance);
+
+            if amount == 0 {
+                return Err(ContractError::ZeroWithdrawal);
+            }
+
+            if amount > account_balance {
+                return Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance);
+            }
+
+            self.accrue_interest(recipient)?;
+            let treasury = self
+                .balance_of(recipient)
+                .checked_add(amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+            self.count_block_op()?;
+
+            self.set_balance(caller, account_balance - amount);
+            self.set_balance(recipient, treasury);
+            self.record_update(caller);
+            self.record_update(recipient);
+
+            self.env().emit_event(Forfeited {
+                from: caller,
+                amount,
+            });
+            self.emit_if_closed(caller, account_balance - amount);
+
+            Ok(amount)
+        }
+
+        #[ink(message)]
+        pub fn treasury(&self) -> Balance {
+            self.balance_of(self.penalty_recipient())
+        }
+
+        /// Where forfeited funds go; the owner unless set otherwise.
+        #[ink(message)]
+        pub fn penalty_recipient(&self) -> AccountId {
+            self.penalty_recipient.unwrap_or(self.owner)
+        }
+
+        #[ink(message)]
+        pub fn set_penalty_recipient(&mut self, recipient: AccountId) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.penalty_recipient = Some(recipient);
+
+            Ok(())
+        }
+
+        /// Moves every non-zero balance below `threshold` among `accounts` to the owner and
+        /// closes those accounts. Frozen accounts are left untouched.
+        #[ink(message)]
+        pub fn sweep_dust(
+            &mut self,
+            accounts: Vec<AccountId>,
+            threshold: Balance,
+        ) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+            let owner = self.owner;
+            let mut swept: Balance = 0;
+
+            for account in accounts {
+                self.accrue_interest(account)?;
+                let account_balance: Balance = self.balance_of(account);
+
+                if account == owner
+                    || account_balance == 0
+                    || account_balance >= threshold
+                    || self.is_frozen(account)
+                {
+                    continue;
+                }
+
+                swept = swept
+                    .checked_add(account_balance)
+                    .ok_or(ContractError::BalanceOverflow)?;
+                self.set_balance(account, 0);
+                self.record_update(account);
+                self.env().emit_event(AccountClosed { account });
+            }
+
+            if swept > 0 {
+                self.accrue_interest(owner)?;
+                let owner_balance = self
+                    .balance_of(owner)
+                    .checked_add(swept)
+                    .ok_or(ContractError::BalanceOverflow)?;
+                self.set_balance(owner, owner_balance);
+                self.record_update(owner);
+            }
+
+            Ok(swept)
+        }
+
+        #[ink(message)]
+        pub fn transfer(&mut self, to: AccountId, amount: Balance) -> Result<(), ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+            self.move_balance(caller, to, amount)
+        }
+
+        #[ink(message)]
+        pub fn transfer_all(&mut self, to: AccountId) -> Result<Balance, ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+
+            if to == caller {
+                return Err(ContractError::SelfTransfer);
+            }
+            if !self.balances.contains(caller) {
+                return Err(ContractError::AccountWithoutBalance);
+            }
+
+            self.accrue_interest(caller)?;
+            let account_balance: Balance = self.balance_of(caller);
+            self.move_balance(caller, to, account_balance)?;
+
+            Ok(account_balance)
+        }
+
+        #[ink(message)]
+        pub fn approve(
+            &mut self,
+            spender: AccountId,
+            amount: Balance,
+        ) -> Result<(), ContractError> {
+            self.count_block_op()?;
+            self.set_allowance(self.get_caller(), spender, amount);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn increase_allowance(
+            &mut self,
+            spender: AccountId,
+            delta: Balance,
+        ) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            let amount = self
+                .allowance(caller, spender)
+                .checked_add(delta)
+                .ok_or(ContractError::BalanceOverflow)?;
+            self.count_block_op()?;
+            self.set_allowance(caller, spender, amount);
+
+            Ok(())
+        }
+
+        /// Lowers the allowance by `delta`, stopping at zero instead of failing.
+        #[ink(message)]
+        pub fn decrease_allowance(
+            &mut self,
+            spender: AccountId,
+            delta: Balance,
+        ) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            let amount = self.allowance(caller, spender).saturating_sub(delta);
+            self.count_block_op()?;
+            self.set_allowance(caller, spender, amount);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+            let allowance: Balance = self.allowance(from, caller);
+
+            if amount > allowance {
+                return Err(ContractError::InsufficientAllowance);
+            }
+
+            self.move_balance(from, to, amount)?;
+            self.allowances
+                .insert((from, caller), &(allowance - amount));
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn admin_credit(
+            &mut self,
+            entries: Vec<(AccountId, Balance)>,
+        ) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if entries.iter().any(|(_, amount)| *amount == 0) {
+                return Err(ContractError::InsufficientFunds);
+            }
+
+            for (account, _) in entries.iter() {
+                self.accrue_interest(*account)?;
+            }
+
+            let mut credited: Vec<(AccountId, Balance)> = Vec::with_capacity(entries.len());
+            let mut total_credited: Balance = 0;
+
+            for (account, amount) in entries.iter() {
+                let account_balance: Balance = credited
+                    .iter()
+                    .rev()
+                    .find(|(credited_account, _)| credited_account == account)
+                    .map(|(_, balance)| *balance)
+                    .unwrap_or_else(|| self.balances.get(account).unwrap_or(0));
+                let new_balance = account_balance
+                    .checked_add(*amount)
+                    .ok_or(ContractError::BalanceOverflow)?;
+                total_credited = total_credited
+                    .checked_add(*amount)
+                    .ok_or(ContractError::BalanceOverflow)?;
+
+                credited.push((*account, new_balance));
+            }
+
+            let total_deposited = self
+                .total_deposited
+                .checked_add(total_credited)
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            for ((account, new_balance), (_, amount)) in credited.iter().zip(entries.iter()) {
+                self.set_balance(*account, *new_balance);
+                self.record_update(*account);
+                self.record_lifetime_deposit(*account, *amount);
+
+                self.env().emit_event(Deposited {
+                    from: *account,
+                    sponsor: self.owner,
+                    balance: *amount,
+                    new_balance: *new_balance,
+                });
+            }
+            self.total_deposited = total_deposited;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn create_stream(
+            &mut self,
+            recipient: AccountId,
+            total: Balance,
+            duration: Timestamp,
+        ) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if self.streams.contains(recipient) {
+                return Err(ContractError::StreamAlreadyExists);
+            }
+
+            self.accrue_interest(self.owner)?;
+            let owner_balance: Balance = self.balance_of(self.owner);
+            if total == 0 || total > owner_balance {
+                return Err(ContractError::InsufficientFunds);
+            }
+
+            self.set_balance(self.owner, owner_balance - total);
+            self.emit_if_closed(self.owner, owner_balance - total);
+            self.streams.insert(
+                recipient,
+                &Stream {
+                    total,
+                    claimed: 0,
+                    start: self.env().block_timestamp(),
+                    duration,
+                },
+            );
+
+            Ok(())
+        }
+
+        /// Pays `immediate_bps` of `amount` out now and moves the rest into a stream for the
+        /// caller that vests linearly over `vest_duration`. The whole `amount` goes through the
+        /// withdrawal checks, fee, daily limit and preauthorization up front, so claiming the
+        /// stream later is free. A zero `vest_duration` pays everything out now.
+        #[ink(message)]
+        pub fn withdraw_with_vesting(
+            &mut self,
+            amount: Balance,
+            immediate_bps: u16,
+            vest_duration: Timestamp,
+        ) -> Result<(), ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+
+            if immediate_bps > MAX_BPS {
+                return Err(ContractError::InvalidPercentage);
+            }
+
+            if self.streams.contains(caller) {
+                return Err(ContractError::StreamAlreadyExists);
+            }
+
+            self.with_cooldown(caller, |contract| {
+                contract.non_reentrant(|contract| {
+                    contract.do_withdraw_with_vesting(caller, amount, immediate_bps, vest_duration)
+                })
+            })
+        }
+
+        #[ink(message)]
+        pub fn stream_of(&self, recipient: AccountId) -> Option<Stream> {
+            self.streams.get(recipient)
+        }
+
+        #[ink(message)]
+        pub fn claim_stream(&mut self) -> Result<Balance, ContractError> {
+            self.non_reentrant(|contract| contract.do_claim_stream())
+        }
+
+        /// Holds the transferred value until the payer settles it to `payee` or refunds it.
+        /// Once disputed, only `arbiter` can decide who gets the funds. After `deadline`
+        /// anyone may refund the payer with `claim_expired`.
+        #[ink(message, payable)]
+        pub fn open_escrow(
+            &mut self,
+            payee: AccountId,
+            arbiter: AccountId,
+            deadline: Timestamp,
+        ) -> Result<u64, ContractError> {
+            self.ensure_not_paused()?;
+            let payer = self.get_caller();
+            self.ensure_not_frozen(payer)?;
+
+            if payee == payer {
+                return Err(ContractError::SelfTransfer);
+            }
+
+            let amount: Balance = self.check_and_get_transferred_funds()?;
+            let total_deposited = self
+                .total_deposited
+                .checked_add(amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+            self.count_block_op()?;
+
+            let id = self.next_escrow_id;
+            self.escrows.insert(
+                id,
+                &Escrow {
+                    payer,
+                    payee,
+                    arbiter,
+                    amount,
+                    released_amount: 0,
+                    released: false,
+                    disputed: false,
+                    deadline,
+                },
+            );
+            self.next_escrow_id = id + 1;
+            self.total_deposited = total_deposited;
+
+            Ok(id)
+        }
+
+        #[ink(message)]
+        pub fn escrow_of(&self, id: u64) -> Option<Escrow> {
+            self.escrows.get(id)
+        }
+
+        /// Releases `amount` to the payee, or everything still escrowed when `None`.
+        #[ink(message)]
+        pub fn settle(&mut self, id: u64, amount: Option<Balance>) -> Result<(), ContractError> {
+            let escrow = self.unresolved_escrow(id)?;
+            if self.get_caller() != escrow.payer {
+                return Err(ContractError::NotPayer);
+            }
+
+            let payee = escrow.payee;
+            self.non_reentrant(|contract| contract.resolve_escrow(id, escrow, payee, amount))
+        }
+
+        #[ink(message)]
+        pub fn refund_escrow(&mut self, id: u64) -> Result<(), ContractError> {
+            let escrow = self.unresolved_escrow(id)?;
+            if self.get_caller() != escrow.payer {
+                return Err(ContractError::NotPayer);
+            }
+
+            if escrow.disputed {
+                return Err(ContractError::EscrowDisputed);
+            }
+
+            let payer = escrow.payer;
+            self.non_reentrant(|contract| contract.resolve_escrow(id, escrow, payer, None))
+        }
+
+        /// Refunds the payer of an escrow left unresolved past its deadline, disputed or not.
+        #[ink(message)]
+        pub fn claim_expired(&mut self, id: u64) -> Result<(), ContractError> {
+            let escrow = self.unresolved_escrow(id)?;
+            if self.env().block_timestamp() <= escrow.deadline {
+                return Err(ContractError::EscrowNotExpired);
+            }
+
+            let payer = escrow.payer;
+            self.non_reentrant(|contract| contract.resolve_escrow(id, escrow, payer, None))
+        }
+
+        #[ink(message)]
+        pub fn dispute(&mut self, id: u64) -> Result<(), ContractError> {
+            let mut escrow = self.unresolved_escrow(id)?;
+            let caller = self.get_caller();
+            if caller != escrow.payer && caller != escrow.payee {
+                return Err(ContractError::NotEscrowParty);
+            }
+
+            self.count_block_op()?;
+            escrow.disputed = true;
+            self.escrows.insert(id, &escrow);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn arbitrate(&mut self, id: u64, pay_payee: bool) -> Result<(), ContractError> {
+            let escrow = self.unresolved_escrow(id)?;
+            if self.get_caller() != escrow.arbiter {
+                return Err(ContractError::NotArbiter);
+            }
+
+            if !escrow.disputed {
+                return Err(ContractError::NotDisputed);
+            }
+
+            let to = if pay_payee {
+                escrow.payee
+            } else {
+                escrow.payer
+            };
+            self.non_reentrant(|contract| contract.resolve_escrow(id, escrow, to, None))
+        }
+
+        #[ink(message, payable)]
+        pub fn deposit_savings(&mut self) -> Result<Balance, ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+            self.ensure_not_frozen(caller)?;
+            let transferred_funds: Balance = self.check_and_get_transferred_funds()?;
+
+            let new_savings = self
+                .savings_of(caller)
+                .checked_add(transferred_funds)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let total_deposited = self
+                .total_deposited
+                .checked_add(transferred_funds)
+                .ok_or(ContractError::BalanceOverflow)?;
+            self.count_block_op()?;
+
+            self.savings.insert(caller, &new_savings);
+            self.last_savings_deposit
+                .insert(caller, &self.env().block_timestamp());
+            self.total_deposited = total_deposited;
+            self.record_lifetime_deposit(caller, transferred_funds);
+
+            Ok(new_savings)
+        }
+
+        #[ink(message)]
+        pub fn withdraw_savings(&mut self, amount: Balance) -> Result<(), ContractError> {
+            self.non_reentrant(|contract| contract.do_withdraw_savings(amount))
+        }
+
+        #[ink(message)]
+        pub fn savings_of(&self, account: AccountId) -> Balance {
+            self.savings.get(account).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn savings_cooldown(&self) -> Timestamp {
+            self.savings_cooldown
+        }
+
+        /// The cooldown is expressed in milliseconds, like `block_timestamp`.
+        #[ink(message)]
+        pub fn set_savings_cooldown(&mut self, cooldown: Timestamp) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.savings_cooldown = cooldown;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn withdraw_cooldown(&self) -> Timestamp {
+            self.withdraw_cooldown
+        }
+
+        /// Milliseconds that must pass between two withdrawals of the same account.
+        #[ink(message)]
+        pub fn set_withdraw_cooldown(&mut self, cooldown: Timestamp) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.withdraw_cooldown = cooldown;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn account_count(&self) -> u32 {
+            self.account_count
+        }
+
+        /// Returns up to `limit` accounts with their balances, starting at index `start`.
+        #[ink(message)]
+        pub fn accounts_page(&self, start: u32, limit: u32) -> Vec<(AccountId, Balance)> {
+            let end = start.saturating_add(limit).min(self.account_count);
+            (start..end)
+                .filter_map(|position| self.account_index.get(position))
+                .map(|account| (account, self.balance_of(account)))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn max_accounts(&self) -> u32 {
+            self.max_accounts
+        }
+
+        /// Caps how many accounts deposits may open; 0 means unlimited.
+        #[ink(message)]
+        pub fn set_max_accounts(&mut self, max_accounts: u32) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.max_accounts = max_accounts;
+
+            Ok(())
+        }
+
+        /// Turning events off skips the `deposit` and `withdraw` events to save weight.
+        #[ink(message)]
+        pub fn set_emit_events(&mut self, emit_events: bool) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.emit_events = emit_events;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_audit_enabled(&mut self, enabled: bool) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.audit_enabled = enabled;
+
+            Ok(())
+        }
+
+        /// Returns up to `limit` of the latest audit entries, newest first.
+        #[ink(message)]
+        pub fn recent_activity(&self, limit: u32) -> Vec<AuditEntry> {
+            self.audit_log
+                .iter()
+                .rev()
+                .take(limit as usize)
+                .copied()
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn total_deposited(&self) -> Balance {
+            self.total_deposited
+        }
+
+        #[ink(message)]
+        pub fn code_hash(&self) -> Option<Hash> {
+            self.env().own_code_hash().ok()
+        }
+
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: Hash) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| ContractError::CodeUpgradeFailed)?;
+
+            self.env().emit_event(CodeUpgraded {
+                new_hash: code_hash,
+            });
+
+            Ok(())
+        }
+
+        /// Swaps the code like `set_code` and bumps `schema_version`, leaving a migration
+        /// pending for the new code to run through `migrate`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.set_code(code_hash)?;
+            self.schema_version += 1;
+            self.migration_pending = true;
+
+            Ok(())
+        }
+
+        /// Called once after `upgrade` by the new code to bring storage to its layout.
+        /// The current layout needs no transformation.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if !self.migration_pending {
+                return Err(ContractError::NoMigrationPending);
+            }
+
+            self.migration_pending = false;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn migration_pending(&self) -> bool {
+            self.migration_pending
+        }
+
+        #[ink(message)]
+        pub fn version(&self) -> u16 {
+            VERSION
+        }
+
+        #[ink(message)]
+        pub fn schema_version(&self) -> u16 {
+            self.schema_version
+        }
+
+        #[ink(message)]
+        pub fn config(&self) -> Config {
+            Config {
+                owner: self.owner,
+                paused: self.paused,
+                fee_bps: self.fee_bps,
+                min_deposit: self.min_deposit,
+                max_balance: self.max_balance,
+                daily_limit: self.daily_limit,
+                emit_events: self.emit_events,
+                fee_tiers: self.fee_tiers.clone(),
+                max_deposit_per_tx: self.max_deposit_per_tx,
+                penalty_recipient: self.penalty_recipient(),
+            }
+        }
+
+        /// `config`, the caller's balance and whether the caller is frozen and whitelisted, in
+        /// one query.
+        #[ink(message)]
+        pub fn bootstrap(&self) -> (Config, Balance, bool, bool) {
+            let caller = self.get_caller();
+
+            (
+                self.config(),
+                self.balance_of(caller),
+                self.is_frozen(caller),
+                self.is_whitelisted(caller),
+            )
+        }
+
+        /// Sum of everything ever deposited by `account`; withdrawals do not lower it.
+        #[ink(message)]
+        pub fn lifetime_deposited_of(&self, account: AccountId) -> Balance {
+            self.lifetime_deposited.get(account).unwrap_or(0)
+        }
+
+        /// Number of (deposits, withdrawals) processed so far.
+        #[ink(message)]
+        pub fn operation_counts(&self) -> (u64, u64) {
+            (self.deposit_count, self.withdraw_count)
+        }
+
+        #[ink(message)]
+        pub fn stats(&self) -> Stats {
+            Stats {
+                total_deposited: self.total_deposited,
+                account_count: self.account_count,
+                contract_balance: self.env().balance(),
+                paused: self.paused,
+                owner: self.owner,
+            }
+        }
+
+        /// Sends out funds that reached the contract without being credited to anyone, such
+        /// as plain transfers. Everything counted in `total_deposited` stays.
+        #[ink(message)]
+        pub fn reconcile_surplus(&mut self, to: AccountId) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+            let surplus: Balance = self.env().balance().saturating_sub(self.total_deposited);
+
+            if surplus == 0 {
+                return Err(ContractError::NoSurplus);
+            }
+
+            self.non_reentrant(|contract| {
+                if contract.env().transfer(to, surplus).is_err() {
+                    return Err(ContractError::WithdrawTransferFailed);
+                }
+
+                Ok(surplus)
+            })
+        }
+
+        #[ink(message)]
+        pub fn solvency(&self) -> bool {
+            self.env().balance() >= self.total_deposited
+        }
+
+        #[ink(message)]
+        pub fn contract_balance(&self) -> Balance {
+            self.env().balance()
+        }
+
+        #[ink(message)]
+        pub fn minimum_deposit(&self) -> Balance {
+            self.min_deposit
+        }
+
+        #[ink(message)]
+        pub fn fee_bps(&self) -> u16 {
+            self.fee_bps
+        }
+
+        #[ink(message)]
+        pub fn set_fee_bps(&mut self, bps: u16) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if bps > MAX_BPS {
+                return Err(ContractError::InvalidFee);
+            }
+
+            self.fee_bps = bps;
+
+            Ok(())
+        }
+
+        /// Withdrawals pay the bps of the highest tier whose threshold they reach, or
+        /// `fee_bps` when no tier applies. An empty schedule restores the flat fee.
+        #[ink(message)]
+        pub fn set_fee_tiers(&mut self, fee_tiers: Vec<FeeTier>) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if !Self::valid_fee_tiers(&fee_tiers) {
+                return Err(ContractError::InvalidFee);
+            }
+
+            self.fee_tiers = fee_tiers;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn fee_tiers(&self) -> Vec<FeeTier> {
+            self.fee_tiers.clone()
+        }
+
+        #[ink(message)]
+        pub fn daily_limit(&self) -> Balance {
+            self.daily_limit
+        }
+
+        #[ink(message)]
+        pub fn outflow_window_limit(&self) -> Balance {
+            self.outflow_window_limit
+        }
+
+        /// Total withdrawn per day across all accounts before the contract pauses itself.
+        #[ink(message)]
+        pub fn set_outflow_window_limit(&mut self, limit: Balance) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.outflow_window_limit = limit;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_daily_limit(&mut self, daily_limit: Balance) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.daily_limit = daily_limit;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_ops_per_block(&self) -> u32 {
+            self.max_ops_per_block
+        }
+
+        /// Caps the user calls that write contract state per block, 0 meaning no cap.
+        /// Batch calls count one operation per leg; owner configuration calls are not counted.
+        #[ink(message)]
+        pub fn set_max_ops_per_block(&mut self, max_ops: u32) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.max_ops_per_block = max_ops;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_deposit_per_tx(&self) -> Balance {
+            self.max_deposit_per_tx
+        }
+
+        #[ink(message)]
+        pub fn set_max_deposit_per_tx(
+            &mut self,
+            max_deposit_per_tx: Balance,
+        ) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.max_deposit_per_tx = max_deposit_per_tx;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_balance(&self) -> Balance {
+            self.max_balance
+        }
+
+        #[ink(message)]
+        pub fn set_max_balance(&mut self, max_balance: Balance) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.max_balance = max_balance;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn reward_pool(&self) -> Balance {
+            self.reward_pool
+        }
+
+        #[ink(message, payable)]
+        pub fn fund_reward_pool(&mut self) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+            let transferred_funds: Balance = self.check_and_get_transferred_funds()?;
+
+            let reward_pool = self
+                .reward_pool
+                .checked_add(transferred_funds)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let total_deposited = self
+                .total_deposited
+                .checked_add(transferred_funds)
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            self.reward_pool = reward_pool;
+            self.total_deposited = total_deposited;
+
+            Ok(reward_pool)
+        }
+
+        #[ink(message)]
+        pub fn fee_to_pool_bps(&self) -> u16 {
+            self.fee_to_pool_bps
+        }
+
+        /// Share of each withdrawal fee that goes to the reward pool instead of the owner.
+        #[ink(message)]
+        pub fn set_fee_to_pool_bps(&mut self, bps: u16) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if bps > MAX_BPS {
+                return Err(ContractError::InvalidFee);
+            }
+
+            self.fee_to_pool_bps = bps;
+
+            Ok(())
+        }
+
+        /// Shares the reward pool among all indexed accounts except the owner, pro rata to
+        /// their balances. Rounding dust stays in the pool.
+        #[ink(message)]
+        pub fn distribute_rewards(&mut self) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+            let owner = self.owner;
+            for (account, _) in self.accounts_page(0, self.account_count) {
+                if account != owner {
+                    self.accrue_interest(account)?;
+                }
+            }
+            let holders: Vec<(AccountId, Balance)> = self
+                .accounts_page(0, self.account_count)
+                .into_iter()
+                .filter(|(account, _)| *account != owner)
+                .collect();
+            let total_held: Balance = holders
+                .iter()
+                .try_fold(0, |total: Balance, (_, balance)| {
+                    total.checked_add(*balance)
+                })
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            if self.reward_pool == 0 || total_held == 0 {
+                return Err(ContractError::NothingToClaim);
+            }
+
+            let mut distributed: Balance = 0;
+            for (account, balance) in holders {
+                let reward = self
+                    .reward_pool
+                    .checked_mul(balance)
+                    .ok_or(ContractError::BalanceOverflow)?
+                    / total_held;
+                if reward == 0 {
+                    continue;
+                }
+
+                self.set_balance(account, balance + reward);
+                self.record_update(account);
+                distributed += reward;
+            }
+            self.reward_pool -= distributed;
+
+            Ok(distributed)
+        }
+
+        #[ink(message)]
+        pub fn referral_bps(&self) -> u16 {
+            self.referral_bps
+        }
+
+        #[ink(message)]
+        pub fn set_referral_bps(&mut self, bps: u16) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if bps > MAX_BPS {
+                return Err(ContractError::InvalidFee);
+            }
+
+            self.referral_bps = bps;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn promo_pool(&self) -> Balance {
+            self.promo_pool
+        }
+
+        #[ink(message, payable)]
+        pub fn fund_promo_pool(&mut self) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+            let transferred_funds: Balance = self.check_and_get_transferred_funds()?;
+
+            let promo_pool = self
+                .promo_pool
+                .checked_add(transferred_funds)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let total_deposited = self
+                .total_deposited
+                .checked_add(transferred_funds)
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            self.promo_pool = promo_pool;
+            self.total_deposited = total_deposited;
+
+            Ok(promo_pool)
+        }
+
+        #[ink(message)]
+        pub fn first_deposit_bonus(&self) -> Balance {
+            self.first_deposit_bonus
+        }
+
+        /// Bonus paid out of the promo pool on an account's first ever deposit.
+        #[ink(message)]
+        pub fn set_first_deposit_bonus(&mut self, bonus: Balance) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.first_deposit_bonus = bonus;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn yield_pool(&self) -> Balance {
+            self.yield_pool
+        }
+
+        #[ink(message, payable)]
+        pub fn fund_yield_pool(&mut self) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+            let transferred_funds: Balance = self.check_and_get_transferred_funds()?;
+
+            let yield_pool = self
+                .yield_pool
+                .checked_add(transferred_funds)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let total_deposited = self
+                .total_deposited
+                .checked_add(transferred_funds)
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            self.yield_pool = yield_pool;
+            self.total_deposited = total_deposited;
+
+            Ok(yield_pool)
+        }
+
+        #[ink(message)]
+        pub fn rate_bps_per_day(&self) -> u16 {
+            self.rate_bps_per_day
+        }
+
+        #[ink(message)]
+        pub fn set_rate_bps_per_day(&mut self, bps: u16) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if bps > MAX_BPS {
+                return Err(ContractError::InvalidFee);
+            }
+
+            self.rate_bps_per_day = bps;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn accrue(&mut self, account: AccountId) -> Result<Balance, ContractError> {
+            self.count_block_op()?;
+            self.accrue_interest(account)
+        }
+
+        /// Interest `accrue` would credit to `account` right now.
+        #[ink(message)]
+        pub fn pending_interest(&self, account: AccountId) -> Balance {
+            let Some(last_accrued) = self.last_accrued.get(account) else {
+                return 0;
+            };
+
+            let days = self.env().block_timestamp().saturating_sub(last_accrued) / MILLIS_PER_DAY;
+            self.interest_owed(self.balance_of(account), days)
+                .unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if new_owner == self.owner || new_owner == AccountId::from([0u8; 32]) {
+                return Err(ContractError::InvalidNewOwner);
+            }
+
+            self.pending_owner = Some(new_owner);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+
+            if self.pending_owner != Some(caller) {
+                return Err(ContractError::NotPendingOwner);
+            }
+
+            let previous = self.owner;
+            self.owner = caller;
+            self.pending_owner = None;
+
+            self.env().emit_event(OwnershipTransferred {
+                previous,
+                new: caller,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_frozen(&self, account: AccountId) -> bool {
+            self.frozen.get(account).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn add_trusted_destination(
+            &mut self,
+            destination: AccountId,
+        ) -> Result<(), ContractError> {
+            self.count_block_op()?;
+            self.trusted.insert((self.get_caller(), destination), &());
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_trusted_destination(
+            &mut self,
+            destination: AccountId,
+        ) -> Result<(), ContractError> {
+            self.count_block_op()?;
+            self.trusted.remove((self.get_caller(), destination));
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_trusted_destination(&self, account: AccountId, destination: AccountId) -> bool {
+            self.trusted.contains((account, destination))
+        }
+
+        /// In strict mode the caller's withdrawals may only go to the caller or to one of its
+        /// trusted destinations.
+        #[ink(message)]
+        pub fn set_strict_withdraw(&mut self, strict: bool) -> Result<(), ContractError> {
+            let caller = self.get_caller();
+            self.count_block_op()?;
+            if strict {
+                self.strict_withdraw.insert(caller, &true);
+            } else {
+                self.strict_withdraw.remove(caller);
+            }
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_strict_withdraw(&self, account: AccountId) -> bool {
+            self.strict_withdraw.get(account).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn is_whitelisted(&self, account: AccountId) -> bool {
+            self.whitelist.get(account).unwrap_or(false)
+        }
+
+        /// While enabled only whitelisted accounts can be credited by deposits; withdrawals
+        /// stay open to everyone.
+        #[ink(message)]
+        pub fn set_whitelist_enabled(&mut self, enabled: bool) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.whitelist_enabled = enabled;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn add_to_whitelist(&mut self, account: AccountId) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.whitelist.insert(account, &true);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_from_whitelist(&mut self, account: AccountId) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.whitelist.remove(account);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn freeze(&mut self, account: AccountId) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.frozen.insert(account, &true);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unfreeze(&mut self, account: AccountId) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+            self.frozen.remove(account);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if self.paused {
+                return Err(ContractError::AlreadyPaused);
+            }
+
+            self.paused = true;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if !self.paused {
+                return Err(ContractError::NotPaused);
+            }
+
+            self.paused = false;
+            // Give the circuit breaker a fresh window once the owner has reviewed the outflow.
+            self.window_outflow = (0, 0);
+
+            Ok(())
+        }
+
+        /// Removes the contract and sends what it holds to the owner. Only allowed while
+        /// paused and once nothing but the owner's balance and pools is left.
+        #[ink(message)]
+        pub fn terminate(&mut self) -> Result<(), ContractError> {
+            self.ensure_owner()?;
+
+            if !self.paused {
+                return Err(ContractError::NotPaused);
+            }
+
+            let owner_funds = self
+                .balance_of(self.owner)
+                .saturating_add(self.reward_pool)
+                .saturating_add(self.yield_pool)
+                .saturating_add(self.promo_pool);
+            if self.total_deposited > owner_funds || self.total_token_deposited > 0 {
+                return Err(ContractError::FundsStillHeld);
+            }
+
+            self.env().terminate_contract(self.owner)
+        }
+
+        /// Break-glass recovery: moves the whole on-chain balance out without touching
+        /// per-account balances, which are left for reconciliation afterwards.
+        #[ink(message)]
+        pub fn emergency_withdraw(&mut self, to: AccountId) -> Result<Balance, ContractError> {
+            self.ensure_owner()?;
+
+            if !self.paused {
+                return Err(ContractError::NotPaused);
+            }
+
+            let amount: Balance = self.env().balance();
+
+            if self.env().transfer(to, amount).is_err() {
+                return Err(ContractError::WithdrawTransferFailed);
+            }
+
+            self.env().emit_event(EmergencyWithdrawn { to, amount });
+
+            Ok(amount)
+        }
+
+        fn get_caller(&self) -> AccountId {
+            self.env().caller()
+        }
+
+        fn move_balance(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), ContractError> {
+            self.ensure_not_frozen(self.get_caller())?;
+            self.ensure_not_frozen(from)?;
+            self.ensure_not_frozen(to)?;
+            self.ensure_unlocked(from)?;
+            self.ensure_trusted_destination(from, to)?;
+
+            if to == from {
+                return Err(ContractError::SelfTransfer);
+            }
+
+            if amount == 0 {
+                return Err(ContractError::InsufficientFunds);
+            }
+
+            self.accrue_interest(from)?;
+            self.accrue_interest(to)?;
+            let from_balance: Balance = self.balances.get(from).unwrap_or(0);
+            if amount > from_balance {
+                return Err(ContractError::InsufficientFunds);
+            }
+
+            let preauthorized = self.preauth.get(from);
+            if preauthorized.is_some_and(|preauthorized| amount > preauthorized) {
+                return Err(ContractError::ExceedsPreauthorization);
+            }
+
+            let to_balance: Balance = self
+                .balances
+                .get(to)
+                .unwrap_or(0)
+                .checked_add(amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+            self.count_block_op()?;
+
+            self.set_balance(from, from_balance - amount);
+            self.set_balance(to, to_balance);
+            if let Some(preauthorized) = preauthorized {
+                self.preauth.insert(from, &(preauthorized - amount));
+            }
+            self.record_activity(from);
+            self.record_activity(to);
+
+            self.env().emit_event(Transferred { from, to, amount });
+            self.emit_if_closed(from, from_balance - amount);
+
+            Ok(())
+        }
+
+        fn do_withdraw(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            withdrawal_amount: Balance,
+        ) -> Result<(), ContractError> {
+            self.do_withdraw_with_fee(from, to, withdrawal_amount, true, true)
+        }
+
+        /// Without `enforce_limits` the account's locks, daily limit and preauthorization
+        /// neither block nor record the withdrawal.
+        fn do_withdraw_with_fee(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            withdrawal_amount: Balance,
+            charge_fee: bool,
+            enforce_limits: bool,
+        ) -> Result<(), ContractError> {
+            if enforce_limits {
+                self.validate_withdrawal(from, to, withdrawal_amount)?;
+            } else {
+                self.validate_payout(from, to, withdrawal_amount)?;
+            }
+            self.count_block_op()?;
+            self.accrue_interest(from)?;
+            let account_balance: Balance = self.balance_of(from);
+            let today = self.env().block_timestamp() / MILLIS_PER_DAY;
+            let withdrawn_today = self.withdrawn_today_of(from) + withdrawal_amount;
+            let preauthorized = self.preauth.get(from);
+
+            let fee: Balance = if charge_fee {
+                self.withdrawal_fee(from, withdrawal_amount)?
+            } else {
+                0
+            };
+            let net_amount: Balance = withdrawal_amount - fee;
+            let pool_fee: Balance = self.pool_share(fee)?;
+            if fee > 0 {
+                self.accrue_interest(self.owner)?;
+            }
+            let owner_balance: Balance = self.balances.get(self.owner).unwrap_or(0);
+            let new_owner_balance = owner_balance
+                .checked_add(fee - pool_fee)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let reward_pool = self.reward_pool;
+            let new_reward_pool = reward_pool
+                .checked_add(pool_fee)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let total_deposited = self
+                .total_deposited
+                .checked_sub(net_amount)
+                .ok_or(ContractError::Insolvent)?;
+
+            self.set_balance(from, account_balance - withdrawal_amount);
+            if fee > 0 {
+                self.set_balance(self.owner, new_owner_balance);
+            }
+            self.reward_pool = new_reward_pool;
+            self.total_deposited = total_deposited;
+
+            let transferred = self.env().transfer(to, net_amount);
+            #[cfg(test)]
+            mock_recipient::on_transfer(self, to);
+            let payout = match transferred {
+                Err(_) => Err(ContractError::WithdrawTransferFailed),
+                Ok(()) => self.ensure_solvent(self.total_deposited),
+            };
+            if let Err(error) = payout {
+                self.set_balance(from, account_balance);
+                if fee > 0 {
+                    self.set_balance(self.owner, owner_balance);
+                }
+                self.reward_pool = reward_pool;
+                self.total_deposited += net_amount;
+                return Err(error);
+            }
+
+            self.withdraw_count = self.withdraw_count.saturating_add(1);
+            if enforce_limits {
+                self.withdrawn_today.insert(from, &(today, withdrawn_today));
+                if let Some(preauthorized) = preauthorized {
+                    self.preauth
+                        .insert(from, &(preauthorized - withdrawal_amount));
+                }
+            }
+            self.record_update(from);
+            self.record_activity(from);
+
+            if self.emit_events {
+                self.env().emit_event(Withdrawn {
+                    to,
+                    balance: net_amount,
+                    new_balance: account_balance - withdrawal_amount,
+                });
+                self.emit_if_closed(from, account_balance - withdrawal_amount);
+            }
+            self.audit(AUDIT_WITHDRAW, from, net_amount);
+            self.track_outflow(today, net_amount);
+
+            Ok(())
+        }
+
+        fn do_withdraw_with_vesting(
+            &mut self,
+            from: AccountId,
+            amount: Balance,
+            immediate_bps: u16,
+            vest_duration: Timestamp,
+        ) -> Result<(), ContractError> {
+            self.validate_withdrawal(from, from, amount)?;
+            self.accrue_interest(from)?;
+
+            let fee: Balance = self.withdrawal_fee(from, amount)?;
+            let net_amount: Balance = amount - fee;
+            let immediate_bps = if vest_duration == 0 {
+                MAX_BPS
+            } else {
+                immediate_bps
+            };
+            let immediate: Balance = net_amount
+                .checked_mul(Balance::from(immediate_bps))
+                .ok_or(ContractError::BalanceOverflow)?
+                / Balance::from(MAX_BPS);
+            let vested: Balance = net_amount - immediate;
+
+            if immediate > 0 {
+                self.do_withdraw_with_fee(from, from, immediate, false, true)?;
+            } else {
+                self.count_block_op()?;
+            }
+
+            // The fee and the vested part are debited here; the daily limit and the
+            // preauthorization already count `immediate`.
+            let held: Balance = amount - immediate;
+            if held == 0 {
+                return Ok(());
+            }
+
+            let pool_fee: Balance = self.pool_share(fee)?;
+            if fee > 0 {
+                self.accrue_interest(self.owner)?;
+            }
+            let owner_balance = self
+                .balance_of(self.owner)
+                .checked_add(fee - pool_fee)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let reward_pool = self
+                .reward_pool
+                .checked_add(pool_fee)
+                .ok_or(ContractError::BalanceOverflow)?;
+            let today = self.env().block_timestamp() / MILLIS_PER_DAY;
+            let withdrawn_today = self.withdrawn_today_of(from) + held;
+            let account_balance: Balance = self.balance_of(from);
+
+            self.set_balance(from, account_balance - held);
+            if fee > 0 {
+                self.set_balance(self.owner, owner_balance);
+            }
+            self.reward_pool = reward_pool;
+            self.withdrawn_today.insert(from, &(today, withdrawn_today));
+            if let Some(preauthorized) = self.preauth.get(from) {
+                self.preauth.insert(from, &(preauthorized - held));
+            }
+            self.record_update(from);
+            self.record_activity(from);
+            self.emit_if_closed(from, account_balance - held);
+
+            if vested > 0 {
+                self.streams.insert(
+                    from,
+                    &Stream {
+                        total: vested,
+                        claimed: 0,
+                        start: self.env().block_timestamp(),
+                        duration: vest_duration,
+                    },
+                );
+            }
+
+            Ok(())
+        }
+
+        fn do_claim_stream(&mut self) -> Result<Balance, ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+            self.ensure_not_frozen(caller)?;
+            let mut stream: Stream = self.streams.get(caller).ok_or(ContractError::NoStream)?;
+
+            let elapsed = self.env().block_timestamp().saturating_sub(stream.start);
+            let vested: Balance = if elapsed >= stream.duration {
+                stream.total
+            } else {
+                stream
+                    .total
+                    .checked_mul(Balance::from(elapsed))
+                    .ok_or(ContractError::BalanceOverflow)?
+                    / Balance::from(stream.duration)
+            };
+            let claimable: Balance = vested - stream.claimed;
+
+            if claimable == 0 {
+                return Err(ContractError::NothingToClaim);
+            }
+
+            let total_deposited = self
+                .total_deposited
+                .checked_sub(claimable)
+                .ok_or(ContractError::Insolvent)?;
+            self.count_block_op()?;
+
+            if self.env().transfer(caller, claimable).is_err() {
+                return Err(ContractError::WithdrawTransferFailed);
+            }
+            self.ensure_solvent(total_deposited)?;
+
+            stream.claimed = vested;
+            if stream.claimed == stream.total {
+                self.streams.remove(caller);
+            } else {
+                self.streams.insert(caller, &stream);
+            }
+            self.total_deposited = total_deposited;
+            self.track_outflow(self.env().block_timestamp() / MILLIS_PER_DAY, claimable);
+
+            self.env().emit_event(Withdrawn {
+                to: caller,
+                balance: claimable,
+                new_balance: stream.total - stream.claimed,
+            });
+
+            Ok(claimable)
+        }
+
+        fn unresolved_escrow(&self, id: u64) -> Result<Escrow, ContractError> {
+            let escrow: Escrow = self.escrows.get(id).ok_or(ContractError::EscrowNotFound)?;
+            if escrow.released {
+                return Err(ContractError::EscrowAlreadyResolved);
+            }
+
+            Ok(escrow)
+        }
+
+        fn resolve_escrow(
+            &mut self,
+            id: u64,
+            mut escrow: Escrow,
+            to: AccountId,
+            amount: Option<Balance>,
+        ) -> Result<(), ContractError> {
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(self.get_caller())?;
+            self.ensure_not_frozen(escrow.payer)?;
+            self.ensure_not_frozen(to)?;
+            let remaining: Balance = escrow.amount - escrow.released_amount;
+            let amount = amount.unwrap_or(remaining);
+
+            if amount == 0 {
+                return Err(ContractError::ZeroWithdrawal);
+            }
+
+            if amount > remaining {
+                return Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance);
+            }
+
+            self.count_block_op()?;
+            self.pay_out(to, amount)?;
+
+            escrow.released_amount += amount;
+            escrow.released = escrow.released_amount == escrow.amount;
+            self.escrows.insert(id, &escrow);
+
+            self.env().emit_event(Withdrawn {
+                to,
+                balance: amount,
+                new_balance: escrow.amount - escrow.released_amount,
+            });
+
+            Ok(())
+        }
+
+        /// Sends `amount` held by the contract to `to` and drops it from `total_deposited`.
+        fn pay_out(&mut self, to: AccountId, amount: Balance) -> Result<(), ContractError> {
+            let total_deposited = self
+                .total_deposited
+                .checked_sub(amount)
+                .ok_or(ContractError::Insolvent)?;
+
+            if self.env().transfer(to, amount).is_err() {
+                return Err(ContractError::WithdrawTransferFailed);
+            }
+            self.ensure_solvent(total_deposited)?;
+            self.total_deposited = total_deposited;
+            self.track_outflow(self.env().block_timestamp() / MILLIS_PER_DAY, amount);
+
+            Ok(())
+        }
+
+        fn do_withdraw_savings(&mut self, amount: Balance) -> Result<(), ContractError> {
+            self.ensure_not_paused()?;
+            let caller = self.get_caller();
+            self.ensure_not_frozen(caller)?;
+            let savings_balance: Balance = self.savings_of(caller);
+
+            if amount == 0 {
+                return Err(ContractError::ZeroWithdrawal);
+            }
+
+            if amount > savings_balance {
+                return Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance);
+            }
+
+            let last_deposit: Timestamp = self.last_savings_deposit.get(caller).unwrap_or(0);
+            if self.env().block_timestamp() < last_deposit.saturating_add(self.savings_cooldown) {
+                return Err(ContractError::SavingsCooldownActive);
+            }
+
+            let total_deposited = self
+                .total_deposited
+                .checked_sub(amount)
+                .ok_or(ContractError::Insolvent)?;
+            self.count_block_op()?;
+
+            if self.env().transfer(caller, amount).is_err() {
+                return Err(ContractError::WithdrawTransferFailed);
+            }
+            self.ensure_solvent(total_deposited)?;
+
+            if savings_balance == amount {
+                self.savings.remove(caller);
+                self.last_savings_deposit.remove(caller);
+            } else {
+                self.savings.insert(caller, &(savings_balance - amount));
+            }
+            self.total_deposited = total_deposited;
+            self.track_outflow(self.env().block_timestamp() / MILLIS_PER_DAY, amount);
+
+            Ok(())
+        }
+
+        /// Checked after a payout; returning an error reverts the transfer as well.
+        fn ensure_solvent(&self, total_deposited: Balance) -> Result<(), ContractError> {
+            if self.env().balance() < total_deposited {
+                return Err(ContractError::Insolvent);
+            }
+            Ok(())
+        }
+
+        /// Every check `do_withdraw` makes before touching state, counting interest that is
+        /// about to accrue as part of the balance.
+        fn validate_withdrawal(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            withdrawal_amount: Balance,
+        ) -> Result<(), ContractError> {
+            self.validate_payout(from, to, withdrawal_amount)?;
+            self.ensure_unlocked(from)?;
+
+            let withdrawn_today = self
+                .withdrawn_today_of(from)
+                .checked_add(withdrawal_amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            if self.daily_limit > 0 && withdrawn_today > self.daily_limit {
+                return Err(ContractError::DailyLimitExceeded);
+            }
+
+            if let Some(preauthorized) = self.preauth.get(from) {
+                if withdrawal_amount > preauthorized {
+                    return Err(ContractError::ExceedsPreauthorization);
+                }
+            }
+
+            Ok(())
+        }
+
+        /// The checks that still apply when the owner forces a payout: the account's own
+        /// locks, daily limit and preauthorization are left out.
+        fn validate_payout(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            withdrawal_amount: Balance,
+        ) -> Result<(), ContractError> {
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(self.get_caller())?;
+            self.ensure_not_frozen(from)?;
+            self.ensure_not_frozen(to)?;
+            self.ensure_trusted_destination(from, to)?;
+            let account_balance: Balance = self
+                .balances
+                .get(from)
+                .ok_or(ContractError::AccountWithoutBalance)?;
+
+            // A stored zero is treated exactly like a missing entry. It is not cleaned up here
+            // since the error reverts any write; `set_balance` never stores zero anyway.
+            if account_balance == 0 {
+                return Err(ContractError::AccountWithoutBalance);
+            }
+
+            if withdrawal_amount == 0 {
+                return Err(ContractError::ZeroWithdrawal);
+            }
+
+            if withdrawal_amount > account_balance + self.pending_interest(from) {
+                return Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance);
+            }
+
+            Ok(())
+        }
+
+        /// Applies `withdraw_cooldown` to user-initiated withdrawals of `account`. Owner
+        /// sweeps such as `force_refund` are not throttled.
+        fn with_cooldown<T>(
+            &mut self,
+            account: AccountId,
+            f: impl FnOnce(&mut Self) -> Result<T, ContractError>,
+        ) -> Result<T, ContractError> {
+            if self.cooldown_active(account) {
+                return Err(ContractError::WithdrawCooldownActive);
+            }
+
+            let result = f(self)?;
+            self.last_withdraw
+                .insert(account, &self.env().block_timestamp());
+
+            Ok(result)
+        }
+
+        fn cooldown_active(&self, account: AccountId) -> bool {
+            match self.last_withdraw.get(account) {
+                Some(last_withdraw) if self.withdraw_cooldown > 0 => {
+                    self.env().block_timestamp().saturating_sub(last_withdraw)
+                        < self.withdraw_cooldown
+                }
+                _ => false,
+            }
+        }
+
+        /// Trips the circuit breaker once today's outflow passes the limit. The withdrawal that
+        /// crosses it still goes through, since failing it would revert the pause as well.
+        fn track_outflow(&mut self, today: Timestamp, amount: Balance) {
+            let (day, outflow) = self.window_outflow;
+            let outflow = if day == today { outflow } else { 0 }.saturating_add(amount);
+            self.window_outflow = (today, outflow);
+
+            if self.outflow_window_limit > 0 && outflow > self.outflow_window_limit {
+                self.paused = true;
+                self.env().emit_event(AutoPaused {
+                    window_outflow: outflow,
+                });
+            }
+        }
+
+        fn count_block_op(&mut self) -> Result<(), ContractError> {
+            let block = self.env().block_number();
+            let (last_block, ops) = self.block_ops;
+            let ops = if last_block == block { ops } else { 0 };
+
+            if self.max_ops_per_block > 0 && ops >= self.max_ops_per_block {
+                return Err(ContractError::RateLimited);
+            }
+
+            self.block_ops = (block, ops + 1);
+
+            Ok(())
+        }
+
+        fn withdrawn_today_of(&self, account: AccountId) -> Balance {
+            let today = self.env().block_timestamp() / MILLIS_PER_DAY;
+            match self.withdrawn_today.get(account) {
+                Some((day, amount)) if day == today => amount,
+                _ => 0,
+            }
+        }
+
+        fn non_reentrant<T>(
+            &mut self,
+            f: impl FnOnce(&mut Self) -> Result<T, ContractError>,
+        ) -> Result<T, ContractError> {
+            if self.locked {
+                return Err(ContractError::ReentrancyDetected);
+            }
+
+            self.locked = true;
+            let result = f(self);
+            self.locked = false;
+
+            result
+        }
+
+        fn credit_deposit(
+            &mut self,
+            account: AccountId,
+            emit_events: bool,
+        ) -> Result<Balance, ContractError> {
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(self.get_caller())?;
+            self.ensure_not_frozen(account)?;
+            let transferred_funds: Balance = self.check_and_get_deposited_funds()?;
+            self.credit_with_events(account, transferred_funds, emit_events)
+        }
+
+        fn credit(
+            &mut self,
+            account: AccountId,
+            amount: Balance,
+        ) -> Result<Balance, ContractError> {
+            self.credit_with_events(account, amount, self.emit_events)
+        }
+
+        fn credit_with_events(
+            &mut self,
+            account: AccountId,
+            amount: Balance,
+            emit_events: bool,
+        ) -> Result<Balance, ContractError> {
+            if self.whitelist_enabled && !self.is_whitelisted(account) {
+                return Err(ContractError::NotWhitelisted);
+            }
+
+            if amount < self.min_deposit {
+                return Err(ContractError::DepositBelowMinimum);
+            }
+
+            if self.max_accounts > 0
+                && !self.balances.contains(account)
+                && self.account_count >= self.max_accounts
+            {
+                return Err(ContractError::AccountLimitReached);
+            }
+
+            self.accrue_interest_with_events(account, emit_events)?;
+            let account_balance: Balance = self.balance_of(account);
+
+            let new_balance = account_balance
+                .checked_add(amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            if self.max_balance > 0 && new_balance > self.max_balance {
+                return Err(ContractError::BalanceCapExceeded);
+            }
+
+            let total_deposited = self
+                .total_deposited
+                .checked_add(amount)
+                .ok_or(ContractError::BalanceOverflow)?;
+            self.count_block_op()?;
+
+            let first_deposit = self.lifetime_deposited_of(account) == 0;
+            self.set_balance(account, new_balance);
+            self.total_deposited = total_deposited;
+            self.record_update(account);
+            self.record_activity(account);
+            self.record_lifetime_deposit(account, amount);
+            self.deposit_count = self.deposit_count.saturating_add(1);
+            self.last_deposit
+                .insert(account, &(self.env().block_timestamp(), amount));
+
+            if emit_events {
+                self.env().emit_event(Deposited {
+                    from: account,
+                    sponsor: self.get_caller(),
+                    balance: amount,
+                    new_balance,
+                });
+            }
+            self.audit(AUDIT_DEPOSIT, account, amount);
+
+            if first_deposit {
+                return self.credit_first_deposit_bonus(account, new_balance, emit_events);
+            }
+
+            Ok(new_balance)
+        }
+
+        /// Skipped silently once the promo pool runs dry.
+        fn credit_first_deposit_bonus(
+            &mut self,
+            account: AccountId,
+            account_balance: Balance,
+            emit_events: bool,
+        ) -> Result<Balance, ContractError> {
+            let bonus = self.first_deposit_bonus.min(self.promo_pool);
+            if bonus == 0 {
+                return Ok(account_balance);
+            }
+
+            let new_balance = account_balance
+                .checked_add(bonus)
+                .ok_or(ContractError::BalanceOverflow)?;
+
+            self.set_balance(account, new_balance);
+            self.promo_pool -= bonus;
+
+            if emit_events {
+                self.env().emit_event(BonusCredited {
+                    account,
+                    amount: bonus,
+                });
+            }
+
+            Ok(new_balance)
+        }
+
+        fn record_lifetime_deposit(&mut self, account: AccountId, amount: Balance) {
+            let lifetime_deposited = self.lifetime_deposited_of(account).saturating_add(amount);
+            self.lifetime_deposited.insert(account, &lifetime_deposited);
+        }
+
+        fn audit(&mut self, tag: u8, account: AccountId, amount: Balance) {
+            if !self.audit_enabled {
+                return;
+            }
+
+            if self.audit_log.len() == AUDIT_LOG_SIZE {
+                self.audit_log.remove(0);
+            }
+            self.audit_log
+                .push((tag, account, amount, self.env().block_timestamp()));
+        }
+
+        fn set_allowance(&mut self, owner: AccountId, spender: AccountId, amount: Balance) {
+            self.allowances.insert((owner, spender), &amount);
+
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                amount,
+            });
+        }
+
+        /// Every balance write goes through here so `account_index` and `account_count`
+        /// follow accounts being opened and closed.
+        fn set_balance(&mut self, account: AccountId, balance: Balance) {
+            let exists = self.balances.contains(account);
+            if balance == 0 {
+                if exists {
+                    self.balances.remove(account);
+                    self.unindex_account(account);
+                }
+            } else {
+                if !exists {
+                    self.account_index.insert(self.account_count, &account);
+                    self.account_positions.insert(account, &self.account_count);
+                    self.account_count += 1;
+                }
+                self.balances.insert(account, &balance);
+            }
+        }
+
+        /// Swaps the last indexed account into the freed slot to keep the index dense.
+        fn unindex_account(&mut self, account: AccountId) {
+            let Some(position) = self.account_positions.take(account) else {
+                return;
+            };
+
+            self.account_count -= 1;
+            if position != self.account_count {
+                if let Some(last) = self.account_index.get(self.account_count) {
+                    self.account_index.insert(position, &last);
+                    self.account_positions.insert(last, &position);
+                }
+            }
+            self.account_index.remove(self.account_count);
+        }
+
+        /// Credits whole elapsed days of interest out of the yield pool, capped by what the
+        /// pool holds. Partial days keep accumulating towards the next accrual.
+        fn accrue_interest(&mut self, account: AccountId) -> Result<Balance, ContractError> {
+            self.accrue_interest_with_events(account, true)
+        }
+
+        fn accrue_interest_with_events(
+            &mut self,
+            account: AccountId,
+            emit_events: bool,
+        ) -> Result<Balance, ContractError> {
+            let now = self.env().block_timestamp();
+            let last_accrued: Timestamp = match self.last_accrued.get(account) {
+                Some(last_accrued) => last_accrued,
+                None => {
+                    self.last_accrued.insert(account, &now);
+                    return Ok(0);
+                }
+            };
+
+            let days = now.saturating_sub(last_accrued) / MILLIS_PER_DAY;
+            if days == 0 {
+                return Ok(0);
+            }
+
+            let account_balance: Balance = self.balance_of(account);
+            let interest = self.interest_owed(account_balance, days)?;
+
+            self.last_accrued
+                .insert(account, &(last_accrued + days * MILLIS_PER_DAY));
+
+            if interest == 0 {
+                return Ok(0);
+            }
+
+            self.set_balance(account, account_balance + interest);
+            self.yield_pool -= interest;
+            self.record_update(account);
+
+            if emit_events {
+                self.env().emit_event(InterestAccrued {
+                    account,
+                    amount: interest,
+                });
+            }
+
+            Ok(interest)
+        }
+
+        /// The whole balance of `account` once pending interest is credited, for withdrawals
+        /// meant to empty the account.
+        fn accrued_balance_of(&mut self, account: AccountId) -> Result<Balance, ContractError> {
+            self.accrue_interest(account)?;
+
+            Ok(self.balance_of(account))
+        }
+
+        fn interest_owed(
+            &self,
+            account_balance: Balance,
+            days: u64,
+        ) -> Result<Balance, ContractError> {
+            let interest: Balance = account_balance
+                .checked_mul(Balance::from(self.rate_bps_per_day))
+                .and_then(|scaled| scaled.checked_mul(Balance::from(days)))
+                .ok_or(ContractError::BalanceOverflow)?
+                / Balance::from(MAX_BPS);
+
+            Ok(interest.min(self.yield_pool))
+        }
+
+        fn record_update(&mut self, account: AccountId) {
+            self.last_update.insert(account, &self.env().block_number());
+        }
+
+        fn record_activity(&mut self, account: AccountId) {
+            self.last_activity_block
+                .insert(account, &self.env().block_number());
+        }
+
+        fn emit_if_closed(&self, account: AccountId, balance: Balance) {
+            if balance == 0 {
+                self.env().emit_event(AccountClosed { account });
+            }
+        }
+
+        /// The fee is credited to the owner, so the owner's own withdrawals are not charged.
+        fn withdrawal_fee(
+            &self,
+            from: AccountId,
+            amount: Balance,
+        ) -> Result<Balance, ContractError> {
+            if from == self.owner {
+                return Ok(0);
+            }
+
+            amount
+                .checked_mul(Balance::from(self.fee_bps_for(amount)))
+                .map(|scaled| scaled / Balance::from(MAX_BPS))
+                .ok_or(ContractError::BalanceOverflow)
+        }
+
+        fn valid_fee_tiers(fee_tiers: &[FeeTier]) -> bool {
+            fee_tiers.windows(2).all(|pair| pair[0].0 < pair[1].0)
+                && fee_tiers.iter().all(|(_, bps)| *bps <= MAX_BPS)
+        }
+
+        /// Part of a withdrawal fee routed to the reward pool; the owner keeps the rest.
+        fn pool_share(&self, fee: Balance) -> Result<Balance, ContractError> {
+            fee.checked_mul(Balance::from(self.fee_to_pool_bps))
+                .map(|scaled| scaled / Balance::from(MAX_BPS))
+                .ok_or(ContractError::BalanceOverflow)
+        }
+
+        fn fee_bps_for(&self, amount: Balance) -> u16 {
+            self.fee_tiers
+                .iter()
+                .rev()
+                .find(|(threshold, _)| *threshold <= amount)
+                .map_or(self.fee_bps, |(_, bps)| *bps)
+        }
+
+        fn ensure_signed_by(
+            &self,
+            signer: AccountId,
+            message_hash: &[u8; 32],
+            signature: &[u8; 65],
+        ) -> Result<(), ContractError> {
+            let public_key = self
+                .env()
+                .ecdsa_recover(signature, message_hash)
+                .map_err(|_| ContractError::BadSignature)?;
+            let recovered_account = self.env().hash_bytes::<Blake2x256>(&public_key);
+
+            if AccountId::from(recovered_account) != signer {
+                return Err(ContractError::BadSignature);
+            }
+
+            Ok(())
+        }
+
+        fn ensure_not_frozen(&self, account: AccountId) -> Result<(), ContractError> {
+            if self.is_frozen(account) {
+                return Err(ContractError::AccountFrozen);
+            }
+
+            Ok(())
+        }
+
+        fn ensure_trusted_destination(
+            &self,
+            from: AccountId,
+            to: AccountId,
+        ) -> Result<(), ContractError> {
+            if from != to && self.is_strict_withdraw(from) && !self.is_trusted_destination(from, to)
+            {
+                return Err(ContractError::UntrustedDestination);
+            }
+
+            Ok(())
+        }
+
+        fn ensure_unlocked(&self, account: AccountId) -> Result<(), ContractError> {
+            if let Some(unlock_at) = self.locks.get(account) {
+                if self.env().block_timestamp() < unlock_at {
+                    return Err(ContractError::FundsLocked);
+                }
+            }
+
+            if let Some(unlock_block) = self.block_locks.get(account) {
+                if self.env().block_number() < unlock_block {
+                    return Err(ContractError::FundsLocked);
+                }
+            }
+
+            Ok(())
+        }
+
+        fn ensure_owner(&self) -> Result<(), ContractError> {
+            if self.get_caller() != self.owner {
+                return Err(ContractError::NotOwner);
+            }
+
+            Ok(())
+        }
+
+        fn ensure_not_paused(&self) -> Result<(), ContractError> {
+            if self.paused {
+                return Err(ContractError::ContractPaused);
+            }
+
+            Ok(())
+        }
+
+        /// Pulls `amount` of `token` from `from` into this contract with `PSP22::transfer_from`.
+        fn pull_token(
+            &self,
+            token: AccountId,
+            from: AccountId,
+            amount: Balance,
+        ) -> Result<(), ContractError> {
+            #[cfg(test)]
+            let pulled = mock_token::transfer(token, from, self.env().account_id(), amount);
+            #[cfg(not(test))]
+            let pulled = build_call::<Environment>()
+                .call(token)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "PSP22::transfer_from"
+                    )))
+                    .push_arg(from)
+                    .push_arg(self.env().account_id())
+                    .push_arg(amount)
+                    .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<Result<(), Psp22Error>>()
+                .try_invoke();
+
+            Self::token_call_result(pulled)
+        }
+
+        /// Sends `amount` of `token` held by this contract to `to` with `PSP22::transfer`.
+        fn send_token(
+            &self,
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), ContractError> {
+            #[cfg(test)]
+            let sent = mock_token::transfer(token, self.env().account_id(), to, amount);
+            #[cfg(not(test))]
+            let sent = build_call::<Environment>()
+                .call(token)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                        .push_arg(to)
+                        .push_arg(amount)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<Result<(), Psp22Error>>()
+                .try_invoke();
+
+            Self::token_call_result(sent)
+        }
+
+        /// Only a call that reached the token and returned `Ok` counts as a transfer.
+        fn token_call_result(
+            result: ink::env::Result<ink::MessageResult<Result<(), Psp22Error>>>,
+        ) -> Result<(), ContractError> {
+            match result {
+                Ok(Ok(Ok(()))) => Ok(()),
+                _ => Err(ContractError::TokenTransferFailed),
+            }
+        }
+
+        fn check_and_get_transferred_funds(&self) -> Result<Balance, ContractError> {
+            let transferred_funds: Balance = self.env().transferred_value();
+            if transferred_funds == 0 {
+                return Err(ContractError::ZeroDeposit);
+            }
+
+            Ok(transferred_funds)
+        }
+
+        /// The transferred value of a deposit into balances, held to `max_deposit_per_tx`.
+        fn check_and_get_deposited_funds(&self) -> Result<Balance, ContractError> {
+            let transferred_funds: Balance = self.check_and_get_transferred_funds()?;
+            if self.max_deposit_per_tx > 0 && transferred_funds > self.max_deposit_per_tx {
+                return Err(ContractError::DepositTooLarge);
+            }
+
+            Ok(transferred_funds)
+        }
+    }
+
+    /// Stands in for a recipient contract whose receive hook calls back into `withdraw`,
+    /// which the off-chain environment cannot do on its own.
+    #[cfg(test)]
+    mod mock_recipient {
+        use super::*;
+        use std::cell::RefCell;
+
+        thread_local! {
+            static ATTACKER: RefCell<Option<(AccountId, Balance)>> = const { RefCell::new(None) };
+            static REENTRY: RefCell<Option<Result<Balance, ContractError>>> = const { RefCell::new(None) };
+        }
+
+        /// Makes the next payout to `attacker` re-enter `withdraw(Some(amount))` once.
+        pub fn arm(attacker: AccountId, amount: Balance) {
+            ATTACKER.with(|armed| *armed.borrow_mut() = Some((attacker, amount)));
+        }
+
+        pub fn reentry_result() -> Option<Result<Balance, ContractError>> {
+            REENTRY.with(|result| result.borrow_mut().take())
+        }
+
+        pub fn on_transfer(contract: &mut Workshop, to: AccountId) {
+            let armed =
+                ATTACKER.with(|armed| armed.borrow().filter(|(attacker, _)| *attacker == to));
+            let Some((attacker, amount)) = armed else {
+                return;
+            };
+            ATTACKER.with(|armed| *armed.borrow_mut() = None);
+
+            let caller = ink::env::caller::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(attacker);
+            let result = contract.withdraw(Some(amount));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(caller);
+
+            REENTRY.with(|reentry| *reentry.borrow_mut() = Some(result));
+        }
+    }
+
+    /// Stands in for the PSP22 token contract, since the off-chain environment cannot call
+    /// other contracts.
+    #[cfg(test)]
+    mod mock_token {
+        use super::*;
+        use std::cell::RefCell;
+
+        /// (token, from, to, amount) of a token transfer.
+        pub type TokenTransfer = (AccountId, AccountId, AccountId, Balance);
+
+        thread_local! {
+            static REJECTS: RefCell<bool> = const { RefCell::new(false) };
+            static TRANSFERS: RefCell<Vec<TokenTransfer>> = const { RefCell::new(Vec::new()) };
+        }
+
+        /// Makes every following transfer fail with a PSP22 error.
+        pub fn reject() {
+            REJECTS.with(|rejects| *rejects.borrow_mut() = true);
+        }
+
+        pub fn transfers() -> Vec<TokenTransfer> {
+            TRANSFERS.with(|transfers| transfers.borrow().clone())
+        }
+
+        pub fn transfer(
+            token: AccountId,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> ink::env::Result<ink::MessageResult<Result<(), Psp22Error>>> {
+            if REJECTS.with(|rejects| *rejects.borrow()) {
+                return Ok(Ok(Err(Psp22Error::InsufficientBalance)));
+            }
+
+            TRANSFERS.with(|transfers| transfers.borrow_mut().push((token, from, to, amount)));
+            Ok(Ok(Ok(())))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        type Event = <Workshop as ::ink::reflect::ContractEventBase>::Type;
+
+        fn get_default_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+            ink::env::test::default_accounts::<ink::env::DefaultEnvironment>()
+        }
+
+        fn init() -> (
+            Workshop,
+            ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>,
+        ) {
+            (Workshop::new(), get_default_accounts())
+        }
+
+        fn set_caller(sender: AccountId) {
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(sender);
+        }
+
+        fn get_chain_balance(account: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(account)
+                .unwrap_or(0)
+        }
+
+        fn set_callee(contract: AccountId) {
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+        }
+
+        fn recorded_events() -> Vec<Event> {
+            ink::env::test::recorded_events()
+                .map(|event| <Event as scale::Decode>::decode(&mut &event.data[..]).unwrap())
+                .collect()
+        }
+
+        fn advance_blocks(count: u32) {
+            for _ in 0..count {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+        }
+
+        fn set_block_timestamp(timestamp: Timestamp) {
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(timestamp);
+        }
+
+        fn set_chain_balance(account: AccountId, balance: Balance) {
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(account, balance);
+        }
+
+        fn transfer_in(value: Balance) {
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(value);
+        }
+
+        fn set_transferred_value(value: Balance) {
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(value);
+        }
+
+        #[ink::test]
+        fn withdraw_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            let balance_amount = 1000;
+            let withdrawal_amount = 600;
+            contract.balances.insert(caller, &balance_amount);
+            contract.total_deposited = balance_amount;
+            set_caller(caller);
+
+            // Act
+            let returned_balance = contract.withdraw(Some(withdrawal_amount)).unwrap();
+            let result = contract.balances.get(caller).unwrap();
+
+            // Assert
+            assert_eq!(result, balance_amount - withdrawal_amount);
+            assert_eq!(returned_balance, result);
+        }
+
+        #[ink::test]
+        fn withdraw_fails() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            set_caller(caller);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::AccountWithoutBalance));
+        }
+
+        #[ink::test]
+        fn deposit_fails_on_balance_overflow() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            let balance_amount = Balance::MAX - 10;
+            contract.balances.insert(caller, &balance_amount);
+            set_caller(caller);
+            set_transferred_value(100);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::BalanceOverflow));
+            assert_eq!(contract.balances.get(caller), Some(balance_amount));
+        }
+
+        #[ink::test]
+        fn transfer_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            let balance_amount = 1000;
+            let transfer_amount = 400;
+            contract.balances.insert(caller, &balance_amount);
+            set_caller(caller);
+
+            // Act
+            contract
+                .transfer(accounts.charlie, transfer_amount)
+                .unwrap();
+
+            // Assert
+            assert_eq!(
+                contract.balances.get(caller),
+                Some(balance_amount - transfer_amount)
+            );
+            assert_eq!(
+                contract.balances.get(accounts.charlie),
+                Some(transfer_amount)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_fails_with_insufficient_funds() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            let balance_amount = 100;
+            contract.balances.insert(caller, &balance_amount);
+            set_caller(caller);
+
+            // Act
+            let result = contract.transfer(accounts.charlie, 101);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InsufficientFunds));
+            assert_eq!(contract.balances.get(caller), Some(balance_amount));
+            assert_eq!(contract.balances.get(accounts.charlie), None);
+        }
+
+        #[ink::test]
+        fn transfer_fails_to_self() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            set_caller(caller);
+
+            // Act
+            let result = contract.transfer(caller, 100);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::SelfTransfer));
+            assert_eq!(contract.balances.get(caller), Some(1000));
+        }
+
+        #[ink::test]
+        fn total_deposited_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+
+            // Act
+            set_caller(accounts.bob);
+            set_transferred_value(300);
+            contract.deposit().unwrap();
+            set_caller(accounts.charlie);
+            set_transferred_value(200);
+            contract.deposit().unwrap();
+            let total_after_deposits = contract.total_deposited();
+            set_caller(accounts.bob);
+            contract.withdraw(Some(100)).unwrap();
+
+            // Assert
+            assert_eq!(total_after_deposits, 500);
+            assert_eq!(contract.total_deposited(), 400);
+        }
+
+        #[ink::test]
+        fn pause_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.pause();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert!(!contract.paused);
+        }
+
+        #[ink::test]
+        fn pause_and_unpause_work() {
+            // Arrange
+            let (mut contract, _) = init();
+
+            // Act
+            contract.pause().unwrap();
+            let paused = contract.is_paused();
+            contract.unpause().unwrap();
+
+            // Assert
+            assert!(paused);
+            assert!(!contract.is_paused());
+        }
+
+        #[ink::test]
+        fn pause_fails_when_already_paused() {
+            // Arrange
+            let (mut contract, _) = init();
+            contract.pause().unwrap();
+
+            // Act
+            let result = contract.pause();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::AlreadyPaused));
+            assert!(contract.is_paused());
+        }
+
+        #[ink::test]
+        fn unpause_fails_when_not_paused() {
+            // Arrange
+            let (mut contract, _) = init();
+
+            // Act
+            let result = contract.unpause();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotPaused));
+            assert!(!contract.is_paused());
+        }
+
+        #[ink::test]
+        fn deposit_fails_while_paused() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.pause().unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::ContractPaused));
+            assert_eq!(contract.balances.get(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn withdraw_to_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            let recipient = accounts.charlie;
+            let balance_amount = 1000;
+            let withdrawal_amount = 300;
+            contract.balances.insert(caller, &balance_amount);
+            contract.total_deposited = balance_amount;
+            let recipient_chain_balance = get_chain_balance(recipient);
+            set_caller(caller);
+
+            // Act
+            contract
+                .withdraw_to(recipient, Some(withdrawal_amount))
+                .unwrap();
+
+            // Assert
+            assert_eq!(
+                contract.balances.get(caller),
+                Some(balance_amount - withdrawal_amount)
+            );
+            assert_eq!(
+                get_chain_balance(recipient),
+                recipient_chain_balance + withdrawal_amount
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_restores_balance_when_transfer_fails() {
+            // Arrange
+            // A callee without a balance record makes every outgoing transfer fail.
+            set_callee(AccountId::from([0x07; 32]));
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            let balance_amount = 1000;
+            contract.balances.insert(caller, &balance_amount);
+            contract.total_deposited = balance_amount;
+            set_caller(caller);
+
+            // Act
+            let result = contract.withdraw(Some(600));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::WithdrawTransferFailed));
+            assert_eq!(contract.balances.get(caller), Some(balance_amount));
+            assert_eq!(contract.total_deposited, balance_amount);
+        }
+
+        #[ink::test]
+        fn solvency_holds_when_deposits_are_backed() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(1000);
+            contract.deposit().unwrap();
+
+            // Act
+            let solvent = contract.solvency();
+
+            // Assert
+            assert!(solvent);
+        }
+
+        #[ink::test]
+        fn withdraw_fails_when_contract_is_insolvent() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            let contract_id = accounts.alice;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_chain_balance(contract_id, 500);
+            set_caller(caller);
+
+            // Act
+            let result = contract.withdraw(Some(300));
+
+            // Assert
+            assert!(!contract.solvency());
+            assert_eq!(result, Err(ContractError::Insolvent));
+            assert_eq!(contract.balances.get(caller), Some(1000));
+            assert_eq!(contract.total_deposited, 1000);
+        }
+
+        #[ink::test]
+        fn transfer_from_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let owner = accounts.bob;
+            let spender = accounts.charlie;
+            contract.balances.insert(owner, &1000);
+            set_caller(owner);
+            contract.approve(spender, 500).unwrap();
+            set_caller(spender);
+
+            // Act
+            contract.transfer_from(owner, accounts.django, 300).unwrap();
+
+            // Assert
+            assert_eq!(contract.balances.get(owner), Some(700));
+            assert_eq!(contract.balances.get(accounts.django), Some(300));
+            assert_eq!(contract.allowance(owner, spender), 200);
+        }
+
+        #[ink::test]
+        fn transfer_from_fails_above_allowance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let owner = accounts.bob;
+            let spender = accounts.charlie;
+            contract.balances.insert(owner, &1000);
+            set_caller(owner);
+            contract.approve(spender, 500).unwrap();
+            set_caller(spender);
+
+            // Act
+            let result = contract.transfer_from(owner, accounts.django, 501);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InsufficientAllowance));
+            assert_eq!(contract.balances.get(owner), Some(1000));
+            assert_eq!(contract.allowance(owner, spender), 500);
+        }
+
+        #[ink::test]
+        fn transfer_from_fails_after_allowance_is_depleted() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let owner = accounts.bob;
+            let spender = accounts.charlie;
+            contract.balances.insert(owner, &1000);
+            set_caller(owner);
+            contract.approve(spender, 500).unwrap();
+            set_caller(spender);
+            contract.transfer_from(owner, accounts.django, 500).unwrap();
+
+            // Act
+            let result = contract.transfer_from(owner, accounts.django, 1);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InsufficientAllowance));
+            assert_eq!(contract.balances.get(owner), Some(500));
+            assert_eq!(contract.allowance(owner, spender), 0);
+        }
+
+        #[ink::test]
+        fn increase_allowance_adds_to_current_allowance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let owner = accounts.bob;
+            let spender = accounts.charlie;
+            set_caller(owner);
+            contract.approve(spender, 500).unwrap();
+
+            // Act
+            contract.increase_allowance(spender, 200).unwrap();
+
+            // Assert
+            assert_eq!(contract.allowance(owner, spender), 700);
+            match recorded_events().last() {
+                Some(Event::Approval(event)) => assert_eq!(event.amount, 700),
+                _ => panic!("expected an Approval event"),
+            }
+        }
+
+        #[ink::test]
+        fn decrease_allowance_saturates_at_zero() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let owner = accounts.bob;
+            let spender = accounts.charlie;
+            set_caller(owner);
+            contract.approve(spender, 500).unwrap();
+
+            // Act
+            contract.decrease_allowance(spender, 800).unwrap();
+
+            // Assert
+            assert_eq!(contract.allowance(owner, spender), 0);
+            match recorded_events().last() {
+                Some(Event::Approval(event)) => assert_eq!(event.amount, 0),
+                _ => panic!("expected an Approval event"),
+            }
+        }
+
+        #[ink::test]
+        fn decreased_allowance_limits_transfer_from() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let owner = accounts.bob;
+            let spender = accounts.charlie;
+            contract.balances.insert(owner, &1000);
+            set_caller(owner);
+            contract.approve(spender, 500).unwrap();
+            contract.decrease_allowance(spender, 200).unwrap();
+            set_caller(spender);
+
+            // Act
+            let result = contract.transfer_from(owner, accounts.django, 301);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InsufficientAllowance));
+            assert_eq!(contract.transfer_from(owner, accounts.django, 300), Ok(()));
+            assert_eq!(contract.allowance(owner, spender), 0);
+        }
+
+        #[ink::test]
+        fn deposit_at_minimum_works() {
+            // Arrange
+            let accounts = get_default_accounts();
+            let mut contract = Workshop::new_with_min(100);
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Ok(100));
+            assert_eq!(contract.minimum_deposit(), 100);
+            assert_eq!(contract.balances.get(accounts.bob), Some(100));
+        }
+
+        #[ink::test]
+        fn deposit_below_minimum_fails() {
+            // Arrange
+            let accounts = get_default_accounts();
+            let mut contract = Workshop::new_with_min(100);
+            set_caller(accounts.bob);
+            set_transferred_value(99);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::DepositBelowMinimum));
+            assert_eq!(contract.balances.get(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn withdraw_full_balance_closes_account() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+
+            // Act
+            contract.withdraw(None).unwrap();
+
+            // Assert
+            assert!(!contract.balances.contains(caller));
+            assert_eq!(
+                contract.get_balance_by_account(),
+                Err(ContractError::AccountWithoutBalance)
+            );
+        }
+
+        #[ink::test]
+        fn admin_credit_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &50);
+            contract.total_deposited = 50;
+            let entries = ink::prelude::vec![
+                (accounts.bob, 100),
+                (accounts.charlie, 200),
+                (accounts.django, 300),
+            ];
+
+            // Act
+            contract.admin_credit(entries).unwrap();
+
+            // Assert
+            assert_eq!(contract.balances.get(accounts.bob), Some(150));
+            assert_eq!(contract.balances.get(accounts.charlie), Some(200));
+            assert_eq!(contract.balances.get(accounts.django), Some(300));
+            assert_eq!(contract.total_deposited(), 650);
+        }
+
+        #[ink::test]
+        fn admin_credit_fails_without_writing_on_zero_amount() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let entries = ink::prelude::vec![(accounts.bob, 100), (accounts.charlie, 0)];
+
+            // Act
+            let result = contract.admin_credit(entries);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InsufficientFunds));
+            assert_eq!(contract.balances.get(accounts.bob), None);
+            assert_eq!(contract.total_deposited(), 0);
+        }
+
+        #[ink::test]
+        fn admin_credit_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.admin_credit(ink::prelude::vec![(accounts.bob, 100)]);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+        }
+
+        #[ink::test]
+        fn balance_of_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let balance_before_deposit = contract.balance_of(accounts.bob);
+            set_caller(accounts.bob);
+            set_transferred_value(250);
+
+            // Act
+            contract.deposit().unwrap();
+
+            // Assert
+            assert_eq!(balance_before_deposit, 0);
+            assert_eq!(contract.balance_of(accounts.bob), 250);
+            assert_eq!(contract.balance_of(accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn withdraw_fails_while_funds_are_locked() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+            contract.deposit_locked(1_000).unwrap();
+            set_block_timestamp(999);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::FundsLocked));
+            assert_eq!(contract.unlock_time(), Some(1_000));
+            assert_eq!(contract.balances.get(accounts.bob), Some(500));
+        }
+
+        #[ink::test]
+        fn transfer_fails_while_funds_are_locked() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+            contract.deposit_locked(1_000).unwrap();
+            set_caller(accounts.charlie);
+            contract.deposit_locked_until_block(5).unwrap();
+
+            // Act
+            let block_locked = contract.transfer_all(accounts.django);
+            set_caller(accounts.bob);
+            let time_locked = contract.transfer(accounts.django, 500);
+
+            // Assert
+            assert_eq!(time_locked, Err(ContractError::FundsLocked));
+            assert_eq!(block_locked, Err(ContractError::FundsLocked));
+            assert!(!contract.has_account(accounts.django));
+        }
+
+        #[ink::test]
+        fn withdraw_works_once_funds_are_unlocked() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+            contract.deposit_locked(1_000).unwrap();
+            set_block_timestamp(1_000);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Ok(0));
+            assert_eq!(contract.balances.get(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn withdraw_fails_on_reentrant_call() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+            // Simulates being inside an ongoing withdrawal.
+            contract.locked = true;
+
+            // Act
+            let result = contract.withdraw(Some(100));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::ReentrancyDetected));
+            assert_eq!(contract.balances.get(caller), Some(1000));
+        }
+
+        #[ink::test]
+        fn withdraw_releases_reentrancy_guard() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+
+            // Act
+            contract.withdraw(Some(100)).unwrap();
+            let failed = contract.withdraw(Some(5000));
+
+            // Assert
+            assert_eq!(
+                failed,
+                Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance)
+            );
+            assert!(!contract.locked);
+        }
+
+        #[ink::test]
+        fn contract_balance_works() {
+            // Arrange
+            let contract_id = AccountId::from([0x07; 32]);
+            set_callee(contract_id);
+            set_chain_balance(contract_id, 0);
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(300);
+
+            // Act
+            contract.deposit().unwrap();
+
+            // Assert
+            assert_eq!(contract.contract_balance(), 300);
+            assert_eq!(contract.contract_balance(), contract.total_deposited());
+        }
+
+        #[ink::test]
+        fn withdraw_up_to_daily_limit_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.set_daily_limit(500).unwrap();
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+
+            // Act
+            contract.withdraw(Some(300)).unwrap();
+            let result = contract.withdraw(Some(200));
+
+            // Assert
+            assert_eq!(result, Ok(500));
+            assert_eq!(contract.balances.get(caller), Some(500));
+        }
+
+        #[ink::test]
+        fn withdraw_over_daily_limit_fails() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.set_daily_limit(500).unwrap();
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+            contract.withdraw(Some(300)).unwrap();
+
+            // Act
+            let result = contract.withdraw(Some(201));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::DailyLimitExceeded));
+            assert_eq!(contract.balances.get(caller), Some(700));
+        }
+
+        #[ink::test]
+        fn daily_limit_resets_on_new_day() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.set_daily_limit(500).unwrap();
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+            contract.withdraw(Some(500)).unwrap();
+            set_block_timestamp(MILLIS_PER_DAY);
+
+            // Act
+            let result = contract.withdraw(Some(500));
+
+            // Assert
+            assert_eq!(result, Ok(0));
+            assert_eq!(contract.balances.get(caller), None);
+        }
+
+        #[ink::test]
+        fn deposit_for_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.alice);
+            set_transferred_value(400);
+
+            // Act
+            contract.deposit_for(accounts.bob).unwrap();
+
+            // Assert
+            assert_eq!(contract.balance_of(accounts.bob), 400);
+            assert_eq!(contract.balance_of(accounts.alice), 0);
+            match recorded_events().last() {
+                Some(Event::Deposited(event)) => {
+                    assert_eq!(event.from, accounts.bob);
+                    assert_eq!(event.sponsor, accounts.alice);
+                    assert_eq!(event.balance, 400);
+                }
+                _ => panic!("expected a Deposited event"),
+            }
+        }
+
+        #[ink::test]
+        fn deposit_split_credits_each_beneficiary() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.alice);
+            set_transferred_value(1000);
+
+            // Act
+            contract
+                .deposit_split(vec![(accounts.bob, 700), (accounts.charlie, 300)])
+                .unwrap();
+
+            // Assert
+            assert_eq!(contract.balance_of(accounts.bob), 700);
+            assert_eq!(contract.balance_of(accounts.charlie), 300);
+            assert_eq!(contract.total_deposited, 1000);
+            let deposits = recorded_events()
+                .into_iter()
+                .filter(|event| matches!(event, Event::Deposited(_)))
+                .count();
+            assert_eq!(deposits, 2);
+        }
+
+        #[ink::test]
+        fn deposit_split_fails_on_share_mismatch() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.alice);
+            set_transferred_value(1000);
+
+            // Act
+            let result = contract.deposit_split(vec![(accounts.bob, 700), (accounts.charlie, 200)]);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::ShareMismatch));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.total_deposited, 0);
+        }
+
+        #[ink::test]
+        fn deposit_fails_without_transferred_funds() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(0);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::ZeroDeposit));
+            assert_eq!(contract.balances.get(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn deposit_returns_new_balance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(150);
+            contract.deposit().unwrap();
+            set_transferred_value(250);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Ok(400));
+            assert_eq!(contract.balance_of(accounts.bob), 400);
+        }
+
+        #[ink::test]
+        fn transfer_ownership_keeps_owner_until_accepted() {
+            // Arrange
+            let (mut contract, accounts) = init();
+
+            // Act
+            contract.transfer_ownership(accounts.bob).unwrap();
+
+            // Assert
+            assert_eq!(contract.owner(), accounts.alice);
+            assert_eq!(contract.pending_owner(), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn accept_ownership_fails_for_third_party() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.transfer_ownership(accounts.bob).unwrap();
+            set_caller(accounts.charlie);
+
+            // Act
+            let result = contract.accept_ownership();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotPendingOwner));
+            assert_eq!(contract.owner(), accounts.alice);
+        }
+
+        #[ink::test]
+        fn accept_ownership_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.transfer_ownership(accounts.bob).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            contract.accept_ownership().unwrap();
+            set_caller(accounts.alice);
+            let pause_by_previous_owner = contract.pause();
+
+            // Assert
+            assert_eq!(contract.owner(), accounts.bob);
+            assert_eq!(contract.pending_owner(), None);
+            assert_eq!(pause_by_previous_owner, Err(ContractError::NotOwner));
+            match recorded_events().last() {
+                Some(Event::OwnershipTransferred(event)) => {
+                    assert_eq!(event.previous, accounts.alice);
+                    assert_eq!(event.new, accounts.bob);
+                }
+                _ => panic!("expected an OwnershipTransferred event"),
+            }
+        }
+
+        #[ink::test]
+        fn transfer_ownership_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.transfer_ownership(accounts.bob);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert_eq!(contract.owner(), accounts.alice);
+        }
+
+        #[ink::test]
+        fn transfer_ownership_fails_to_current_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+
+            // Act
+            let result = contract.transfer_ownership(accounts.alice);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InvalidNewOwner));
+        }
+
+        #[ink::test]
+        fn transfer_ownership_fails_to_zero_account() {
+            // Arrange
+            let (mut contract, accounts) = init();
+
+            // Act
+            let result = contract.transfer_ownership(AccountId::from([0u8; 32]));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InvalidNewOwner));
+            assert_eq!(contract.owner(), accounts.alice);
+        }
+
+        #[ink::test]
+        fn deposit_up_to_balance_cap_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_balance(1000).unwrap();
+            contract.balances.insert(accounts.bob, &600);
+            set_caller(accounts.bob);
+            set_transferred_value(400);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Ok(1000));
+        }
+
+        #[ink::test]
+        fn deposit_over_balance_cap_fails() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_balance(1000).unwrap();
+            contract.balances.insert(accounts.bob, &600);
+            set_caller(accounts.charlie);
+            set_transferred_value(401);
+
+            // Act
+            let result = contract.deposit_for(accounts.bob);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::BalanceCapExceeded));
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+        }
+
+        #[ink::test]
+        fn deposit_without_balance_cap_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &(Balance::MAX / 2));
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(contract.max_balance(), 0);
+            assert_eq!(result, Ok(Balance::MAX / 2 + 1000));
+        }
+
+        fn withdraw_percent_of(percent: u8) -> (Result<Balance, ContractError>, Balance) {
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+
+            let result = contract.withdraw_percent(percent);
+
+            (result, contract.balance_of(caller))
+        }
+
+        #[ink::test]
+        fn withdraw_zero_percent_fails() {
+            assert_eq!(
+                withdraw_percent_of(0),
+                (Err(ContractError::ZeroWithdrawal), 1000)
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_fifty_percent_works() {
+            assert_eq!(withdraw_percent_of(50), (Ok(500), 500));
+        }
+
+        #[ink::test]
+        fn withdraw_hundred_percent_works() {
+            assert_eq!(withdraw_percent_of(100), (Ok(1000), 0));
+        }
+
+        #[ink::test]
+        fn withdraw_percent_fails_above_hundred() {
+            assert_eq!(
+                withdraw_percent_of(101),
+                (Err(ContractError::InvalidPercentage), 1000)
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_entry_points_fail_alike_without_balance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.alice);
+            let expected = Err(ContractError::AccountWithoutBalance);
+
+            // Act & Assert
+            assert_eq!(contract.withdraw(None), expected);
+            assert_eq!(
+                contract.withdraw_to(accounts.bob, None).map(|_| 0),
+                expected
+            );
+            assert_eq!(contract.withdraw_percent(50), expected);
+            assert_eq!(contract.owner_withdraw(accounts.bob), expected);
+        }
+
+        #[ink::test]
+        fn withdraw_entry_points_fail_alike_on_overdraw() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &100);
+            contract.total_deposited = 100;
+            set_caller(caller);
+            let expected = Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance);
+
+            // Act & Assert
+            assert_eq!(contract.withdraw(Some(101)), expected);
+            assert_eq!(
+                contract.withdraw_to(accounts.charlie, Some(101)).map(|_| 0),
+                expected
+            );
+            assert_eq!(contract.balance_of(caller), 100);
+            assert!(!contract.locked);
+        }
+
+        #[ink::test]
+        fn config_works() {
+            // Arrange
+            let accounts = get_default_accounts();
+            let mut contract = Workshop::new_with_min(10);
+            contract.set_fee_bps(250).unwrap();
+            contract.set_max_balance(5000).unwrap();
+            contract.set_daily_limit(1000).unwrap();
+            contract.pause().unwrap();
+
+            // Act
+            let config = contract.config();
+
+            // Assert
+            assert_eq!(
+                config,
+                Config {
+                    owner: accounts.alice,
+                    paused: true,
+                    fee_bps: 250,
+                    min_deposit: 10,
+                    max_balance: 5000,
+                    daily_limit: 1000,
+                    emit_events: true,
+                    fee_tiers: Vec::new(),
+                    max_deposit_per_tx: 0,
+                    penalty_recipient: accounts.alice,
+                }
+            );
+        }
+
+        fn init_with_stream(
+            total: Balance,
+            duration: Timestamp,
+        ) -> (
+            Workshop,
+            ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>,
+        ) {
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.alice, &total);
+            contract.total_deposited = total;
+            contract
+                .create_stream(accounts.bob, total, duration)
+                .unwrap();
+            (contract, accounts)
+        }
+
+        #[ink::test]
+        fn create_stream_fails_without_owner_funds() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.alice, &100);
+
+            // Act
+            let result = contract.create_stream(accounts.bob, 101, 1_000);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InsufficientFunds));
+            assert_eq!(contract.stream_of(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn claim_stream_works_partway() {
+            // Arrange
+            let (mut contract, accounts) = init_with_stream(1000, 1_000);
+            set_caller(accounts.bob);
+            set_block_timestamp(250);
+
+            // Act
+            let claimed = contract.claim_stream();
+
+            // Assert
+            assert_eq!(claimed, Ok(250));
+            assert_eq!(contract.balance_of(accounts.alice), 0);
+            assert_eq!(contract.stream_of(accounts.bob).unwrap().claimed, 250);
+            assert_eq!(contract.total_deposited(), 750);
+        }
+
+        #[ink::test]
+        fn claim_stream_works_when_fully_vested() {
+            // Arrange
+            let (mut contract, accounts) = init_with_stream(1000, 1_000);
+            set_caller(accounts.bob);
+            set_block_timestamp(400);
+            contract.claim_stream().unwrap();
+            set_block_timestamp(5_000);
+
+            // Act
+            let claimed = contract.claim_stream();
+
+            // Assert
+            assert_eq!(claimed, Ok(600));
+            assert_eq!(contract.stream_of(accounts.bob), None);
+            assert_eq!(contract.total_deposited(), 0);
+        }
+
+        #[ink::test]
+        fn claim_stream_fails_with_nothing_vested() {
+            // Arrange
+            let (mut contract, accounts) = init_with_stream(1000, 1_000);
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.claim_stream();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NothingToClaim));
+        }
+
+        #[ink::test]
+        fn has_account_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+
+            // Act
+            contract.deposit().unwrap();
+
+            // Assert
+            assert!(contract.has_account(accounts.bob));
+            assert!(!contract.has_account(accounts.charlie));
+        }
+
+        fn contract_id() -> AccountId {
+            ink::env::account_id::<ink::env::DefaultEnvironment>()
+        }
+
+        fn signer_account(secret_key: &secp256k1::SecretKey) -> AccountId {
+            let public_key = secret_key.public_key(secp256k1::SECP256K1).serialize();
+            let mut account = [0u8; 32];
+            ink::env::hash_bytes::<Blake2x256>(&public_key, &mut account);
+            AccountId::from(account)
+        }
+
+        fn sign<T: scale::Encode>(secret_key: &secp256k1::SecretKey, payload: &T) -> [u8; 65] {
+            let mut message_hash = [0u8; 32];
+            ink::env::hash_encoded::<Blake2x256, _>(payload, &mut message_hash);
+            let message = secp256k1::Message::from_slice(&message_hash).unwrap();
+            let (recovery_id, compact) = secp256k1::SECP256K1
+                .sign_ecdsa_recoverable(&message, secret_key)
+                .serialize_compact();
+
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+            signature
+        }
+
+        fn init_with_signer() -> (
+            Workshop,
+            ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>,
+            secp256k1::SecretKey,
+            AccountId,
+        ) {
+            let (mut contract, accounts) = init();
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x2a; 32]).unwrap();
+            let signer = signer_account(&secret_key);
+            contract.balances.insert(signer, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.eve);
+            (contract, accounts, secret_key, signer)
+        }
+
+        #[ink::test]
+        fn withdraw_signed_works() {
+            // Arrange
+            let (mut contract, _, secret_key, signer) = init_with_signer();
+            let signature = sign(&secret_key, &(contract_id(), signer, 400u128, 0u64));
+
+            // Act
+            let result = contract.withdraw_signed(signer, 400, 0, signature);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(signer), 600);
+            assert_eq!(get_chain_balance(signer), 400);
+            assert_eq!(contract.nonce_of(signer), 1);
+        }
+
+        #[ink::test]
+        fn withdraw_signed_fails_with_wrong_signature() {
+            // Arrange
+            let (mut contract, _, _, signer) = init_with_signer();
+            let other_key = secp256k1::SecretKey::from_slice(&[0x2b; 32]).unwrap();
+            let signature = sign(&other_key, &(contract_id(), signer, 400u128, 0u64));
+
+            // Act
+            let result = contract.withdraw_signed(signer, 400, 0, signature);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::BadSignature));
+            assert_eq!(contract.balance_of(signer), 1000);
+            assert_eq!(contract.nonce_of(signer), 0);
+        }
+
+        #[ink::test]
+        fn withdraw_signed_fails_with_signature_for_other_contract() {
+            // Arrange
+            let (mut contract, accounts, secret_key, signer) = init_with_signer();
+            let signature = sign(&secret_key, &(accounts.frank, signer, 400u128, 0u64));
+
+            // Act
+            let result = contract.withdraw_signed(signer, 400, 0, signature);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::BadSignature));
+            assert_eq!(contract.balance_of(signer), 1000);
+        }
+
+        #[ink::test]
+        fn withdraw_signed_fails_on_replayed_nonce() {
+            // Arrange
+            let (mut contract, _, secret_key, signer) = init_with_signer();
+            let signature = sign(&secret_key, &(contract_id(), signer, 400u128, 0u64));
+            contract.withdraw_signed(signer, 400, 0, signature).unwrap();
+
+            // Act
+            let result = contract.withdraw_signed(signer, 400, 0, signature);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InvalidNonce));
+            assert_eq!(contract.balance_of(signer), 600);
+        }
+
+        #[ink::test]
+        fn emergency_withdraw_fails_when_not_paused() {
+            // Arrange
+            let (mut contract, accounts) = init();
+
+            // Act
+            let result = contract.emergency_withdraw(accounts.django);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotPaused));
+            assert_eq!(get_chain_balance(accounts.django), 0);
+        }
+
+        #[ink::test]
+        fn emergency_withdraw_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.pause().unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.emergency_withdraw(accounts.bob);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+        }
+
+        #[ink::test]
+        fn emergency_withdraw_moves_full_balance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            contract.pause().unwrap();
+            let contract_balance = contract.contract_balance();
+
+            // Act
+            let result = contract.emergency_withdraw(accounts.django);
+
+            // Assert
+            assert_eq!(result, Ok(contract_balance));
+            assert_eq!(contract.contract_balance(), 0);
+            assert_eq!(get_chain_balance(accounts.django), contract_balance);
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn withdraw_fails_before_unlock_block() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+            contract.deposit_locked_until_block(5).unwrap();
+            advance_blocks(4);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::FundsLocked));
+            assert_eq!(contract.unlock_block(), Some(5));
+        }
+
+        #[ink::test]
+        fn withdraw_works_at_unlock_block() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+            contract.deposit_locked_until_block(5).unwrap();
+            advance_blocks(5);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Ok(0));
+        }
+
+        #[ink::test]
+        fn withdraw_requires_both_locks_to_expire() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+            contract.deposit_locked_until_block(5).unwrap();
+            contract.deposit_locked(1_000_000).unwrap();
+            advance_blocks(5);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::FundsLocked));
+        }
+
+        #[ink::test]
+        fn withdraw_zero_amount_fails_without_event() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+            let events_before = recorded_events().len();
+
+            // Act
+            let result = contract.withdraw(Some(0));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::ZeroWithdrawal));
+            assert_eq!(recorded_events().len(), events_before);
+            assert_eq!(contract.withdraw(None), Ok(0));
+        }
+
+        #[ink::test]
+        fn multi_withdraw_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+            let events_before = recorded_events().len();
+
+            // Act
+            contract
+                .multi_withdraw(ink::prelude::vec![
+                    (accounts.django, 100),
+                    (accounts.eve, 200)
+                ])
+                .unwrap();
+
+            // Assert
+            assert_eq!(contract.balance_of(caller), 700);
+            assert_eq!(contract.total_deposited(), 700);
+            assert_eq!(get_chain_balance(accounts.django), 100);
+            assert_eq!(get_chain_balance(accounts.eve), 200);
+            let withdrawals = recorded_events()
+                .into_iter()
+                .skip(events_before)
+                .filter(|event| matches!(event, Event::Withdrawn(_)))
+                .count();
+            assert_eq!(withdrawals, 2);
+        }
+
+        #[ink::test]
+        fn multi_withdraw_fails_with_insufficient_total_balance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+
+            // Act
+            let result = contract.multi_withdraw(ink::prelude::vec![
+                (accounts.django, 600),
+                (accounts.eve, 500)
+            ]);
+
+            // Assert
+            assert_eq!(
+                result,
+                Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance)
+            );
+            assert_eq!(contract.balance_of(caller), 1000);
+            assert_eq!(get_chain_balance(accounts.django), 0);
+        }
+
+        #[ink::test]
+        fn multi_withdraw_rolls_back_the_whole_debit() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.set_daily_limit(500).unwrap();
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+
+            // Act
+            let result = contract.multi_withdraw(ink::prelude::vec![
+                (accounts.django, 300),
+                (accounts.eve, 300)
+            ]);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::DailyLimitExceeded));
+            assert_eq!(contract.balance_of(caller), 1000);
+            assert_eq!(contract.total_deposited(), 1000);
+            assert_eq!(contract.withdrawn_today.get(caller), None);
+        }
+
+        #[ink::test]
+        fn withdraw_full_balance_emits_account_closed() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+            let events_before = recorded_events().len();
+
+            // Act
+            contract.withdraw(None).unwrap();
+
+            // Assert
+            let events = recorded_events();
+            assert_eq!(events.len(), events_before + 2);
+            assert!(matches!(
+                &events[events_before],
+                Event::Withdrawn(event) if event.to == caller && event.balance == 1000
+            ));
+            assert!(matches!(
+                &events[events_before + 1],
+                Event::AccountClosed(event) if event.account == caller
+            ));
+        }
+
+        #[ink::test]
+        fn partial_withdraw_keeps_account_open() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            contract.total_deposited = 1000;
+            set_caller(caller);
+
+            // Act
+            contract.withdraw(Some(400)).unwrap();
+
+            // Assert
+            assert!(!recorded_events()
+                .iter()
+                .any(|event| matches!(event, Event::AccountClosed(_))));
+        }
+
+        #[ink::test]
+        fn transfer_full_balance_emits_account_closed() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let caller = accounts.bob;
+            contract.balances.insert(caller, &1000);
+            set_caller(caller);
+
+            // Act
+            contract.transfer(accounts.charlie, 1000).unwrap();
+
+            // Assert
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::AccountClosed(event)) if event.account == caller
+            ));
+            assert!(!contract.has_account(caller));
+        }
+
+        #[ink::test]
+        fn account_info_records_last_update_block() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            advance_blocks(3);
+            set_transferred_value(500);
+            contract.deposit().unwrap();
+            advance_blocks(4);
+
+            // Act
+            let info_after_deposit = contract.account_info(accounts.bob);
+            contract.withdraw(Some(100)).unwrap();
+            let info_after_withdraw = contract.account_info(accounts.bob);
+
+            // Assert
+            assert_eq!(info_after_deposit, Some((500, 3)));
+            assert_eq!(info_after_withdraw, Some((400, 7)));
+            assert_eq!(contract.account_info(accounts.charlie), None);
+        }
+
+        #[ink::test]
+        fn force_refund_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let account = accounts.django;
+            contract.balances.insert(account, &700);
+            contract.total_deposited = 700;
+
+            // Act
+            let refunded = contract.force_refund(account);
+
+            // Assert
+            assert_eq!(refunded, Ok(700));
+            assert_eq!(get_chain_balance(account), 700);
+            assert!(!contract.has_account(account));
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::AccountClosed(event)) if event.account == account
+            ));
+        }
+
+        #[ink::test]
+        fn force_refund_charges_no_fee() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(1000).unwrap();
+            let account = accounts.django;
+            contract.balances.insert(account, &1000);
+            contract.total_deposited = 1000;
+
+            // Act
+            let refunded = contract.force_refund(account);
+
+            // Assert
+            assert_eq!(refunded, Ok(1000));
+            assert_eq!(get_chain_balance(account), 1000);
+            assert_eq!(contract.balance_of(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn force_refund_ignores_account_limits() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_daily_limit(100).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit_locked(5_000).unwrap();
+            contract.preauthorize(0).unwrap();
+            set_caller(accounts.alice);
+
+            // Act
+            let refunded = contract.force_refund(accounts.bob);
+
+            // Assert
+            assert_eq!(refunded, Ok(1000));
+            assert!(!contract.has_account(accounts.bob));
+            assert_eq!(contract.preauthorization_of(accounts.bob), Some(0));
+        }
+
+        #[ink::test]
+        fn force_refund_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let account = accounts.django;
+            contract.balances.insert(account, &700);
+            contract.total_deposited = 700;
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.force_refund(account);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert_eq!(contract.balance_of(account), 700);
+        }
+
+        #[ink::test]
+        fn version_works() {
+            // Arrange
+            let (contract, _) = init();
+
+            // Act & Assert
+            assert_eq!(contract.version(), VERSION);
+            assert_eq!(contract.schema_version(), VERSION);
+        }
+
+        #[ink::test]
+        fn withdraw_savings_fails_during_cooldown() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_savings_cooldown(10_000).unwrap();
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            set_transferred_value(500);
+            contract.deposit_savings().unwrap();
+            set_block_timestamp(10_999);
+
+            // Act
+            let result = contract.withdraw_savings(200);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::SavingsCooldownActive));
+            assert_eq!(contract.savings_of(accounts.bob), 500);
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn withdraw_savings_works_after_cooldown() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_savings_cooldown(10_000).unwrap();
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            set_transferred_value(500);
+            contract.deposit_savings().unwrap();
+            let chain_balance = get_chain_balance(accounts.bob);
+            set_block_timestamp(11_000);
+
+            // Act
+            let result = contract.withdraw_savings(200);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.savings_of(accounts.bob), 300);
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 200);
+            assert_eq!(contract.total_deposited(), 300);
+        }
+
+        #[ink::test]
+        fn withdraw_fails_during_withdraw_cooldown() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_withdraw_cooldown(10_000).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            contract.withdraw(Some(100)).unwrap();
+
+            // Act
+            let result = contract.withdraw(Some(100));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::WithdrawCooldownActive));
+            assert_eq!(contract.balance_of(accounts.bob), 900);
+        }
+
+        #[ink::test]
+        fn withdraw_works_after_withdraw_cooldown() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_withdraw_cooldown(10_000).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            contract.withdraw(Some(100)).unwrap();
+            set_block_timestamp(11_000);
+
+            // Act
+            let result = contract.withdraw(Some(100));
+
+            // Assert
+            assert_eq!(result, Ok(800));
+        }
+
+        #[ink::test]
+        fn deposit_with_referrer_pays_bonus_from_pool() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_referral_bps(500).unwrap();
+            set_transferred_value(1000);
+            contract.fund_reward_pool().unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(400);
+
+            // Act
+            contract.deposit_with_referrer(accounts.charlie).unwrap();
+
+            // Assert
+            assert_eq!(contract.balance_of(accounts.bob), 400);
+            assert_eq!(contract.balance_of(accounts.charlie), 20);
+            assert_eq!(contract.reward_pool(), 980);
+            assert_eq!(contract.total_deposited(), 1400);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::Referred(event))
+                    if event.referrer == accounts.charlie && event.amount == 20
+            ));
+        }
+
+        #[ink::test]
+        fn deposit_with_referrer_works_with_empty_pool() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_referral_bps(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(400);
+
+            // Act
+            let result = contract.deposit_with_referrer(accounts.charlie);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 400);
+            assert_eq!(contract.balance_of(accounts.charlie), 0);
+            assert!(!recorded_events()
+                .iter()
+                .any(|event| matches!(event, Event::Referred(_))));
+        }
+
+        #[ink::test]
+        fn frozen_account_cannot_deposit_or_withdraw() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let frozen = accounts.bob;
+            contract.balances.insert(frozen, &1000);
+            contract.total_deposited = 1000;
+            contract.freeze(frozen).unwrap();
+            set_caller(frozen);
+            set_transferred_value(100);
+
+            // Act
+            let deposit = contract.deposit();
+            let withdraw = contract.withdraw(None);
+            set_caller(accounts.charlie);
+            let deposit_for_frozen = contract.deposit_for(frozen);
+
+            // Assert
+            assert!(contract.is_frozen(frozen));
+            assert_eq!(deposit, Err(ContractError::AccountFrozen));
+            assert_eq!(withdraw, Err(ContractError::AccountFrozen));
+            assert_eq!(deposit_for_frozen, Err(ContractError::AccountFrozen));
+            assert_eq!(contract.balance_of(frozen), 1000);
+        }
+
+        #[ink::test]
+        fn transfer_to_frozen_account_fails() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.charlie, &1000);
+            contract.freeze(accounts.bob).unwrap();
+            set_caller(accounts.charlie);
+
+            // Act
+            let result = contract.transfer(accounts.bob, 100);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::AccountFrozen));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn unfreeze_restores_access() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let account = accounts.bob;
+            contract.balances.insert(account, &1000);
+            contract.total_deposited = 1000;
+            contract.freeze(account).unwrap();
+            contract.unfreeze(account).unwrap();
+            set_caller(account);
+            set_transferred_value(100);
+
+            // Act
+            let deposit = contract.deposit();
+            let withdraw = contract.withdraw(None);
+
+            // Assert
+            assert!(!contract.is_frozen(account));
+            assert_eq!(deposit, Ok(1100));
+            assert_eq!(withdraw, Ok(0));
+        }
+
+        #[ink::test]
+        fn freeze_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.freeze(accounts.charlie);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert!(!contract.is_frozen(accounts.charlie));
+        }
+
+        fn init_with_interest(
+            rate_bps_per_day: u16,
+            yield_pool: Balance,
+        ) -> (
+            Workshop,
+            ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>,
+        ) {
+            let (mut contract, accounts) = init();
+            contract.set_rate_bps_per_day(rate_bps_per_day).unwrap();
+            set_transferred_value(yield_pool);
+            contract.fund_yield_pool().unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(10_000);
+            contract.deposit().unwrap();
+            (contract, accounts)
+        }
+
+        #[ink::test]
+        fn accrue_credits_interest() {
+            // Arrange
+            let (mut contract, accounts) = init_with_interest(10, 1_000);
+            set_block_timestamp(3 * MILLIS_PER_DAY + 1);
+
+            // Act
+            let interest = contract.accrue(accounts.bob);
+
+            // Assert
+            assert_eq!(interest, Ok(30));
+            assert_eq!(contract.balance_of(accounts.bob), 10_030);
+            assert_eq!(contract.yield_pool(), 970);
+            assert_eq!(contract.accrue(accounts.bob), Ok(0));
+        }
+
+        #[ink::test]
+        fn accrue_is_capped_by_yield_pool() {
+            // Arrange
+            let (mut contract, accounts) = init_with_interest(10, 25);
+            set_block_timestamp(3 * MILLIS_PER_DAY);
+
+            // Act
+            let interest = contract.accrue(accounts.bob);
+
+            // Assert
+            assert_eq!(interest, Ok(25));
+            assert_eq!(contract.balance_of(accounts.bob), 10_025);
+            assert_eq!(contract.yield_pool(), 0);
+        }
+
+        #[ink::test]
+        fn withdraw_all_includes_accrued_interest() {
+            // Arrange
+            let (mut contract, accounts) = init_with_interest(10, 1_000);
+            set_block_timestamp(3 * MILLIS_PER_DAY + 1);
+            let chain_balance = get_chain_balance(accounts.bob);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Ok(0));
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 10_030);
+            assert!(!contract.has_account(accounts.bob));
+            assert!(recorded_events()
+                .into_iter()
+                .any(|event| matches!(event, Event::AccountClosed(_))));
+        }
+
+        #[ink::test]
+        fn force_refund_includes_accrued_interest() {
+            // Arrange
+            let (mut contract, accounts) = init_with_interest(10, 1_000);
+            set_block_timestamp(3 * MILLIS_PER_DAY + 1);
+            set_caller(accounts.alice);
+
+            // Act
+            let refunded = contract.force_refund(accounts.bob);
+
+            // Assert
+            assert_eq!(refunded, Ok(10_030));
+            assert!(!contract.has_account(accounts.bob));
+        }
+
+        #[ink::test]
+        fn transfer_settles_interest_of_both_accounts_first() {
+            // Arrange
+            let (mut contract, accounts) = init_with_interest(10, 5_000);
+            set_caller(accounts.charlie);
+            set_transferred_value(1_000);
+            contract.deposit().unwrap();
+            set_block_timestamp(10 * MILLIS_PER_DAY);
+            set_caller(accounts.bob);
+
+            // Act
+            let transferred = contract.transfer_all(accounts.charlie);
+
+            // Assert
+            assert_eq!(transferred, Ok(10_100));
+            assert_eq!(contract.balance_of(accounts.charlie), 11_110);
+            assert_eq!(contract.accrue(accounts.charlie), Ok(0));
+            assert_eq!(contract.yield_pool(), 4_890);
+        }
+
+        #[ink::test]
+        fn deposit_accrues_interest_first() {
+            // Arrange
+            let (mut contract, accounts) = init_with_interest(10, 1_000);
+            set_block_timestamp(MILLIS_PER_DAY);
+            set_transferred_value(500);
+
+            // Act
+            let balance = contract.deposit();
+
+            // Assert
+            assert_eq!(balance, Ok(10_510));
+            assert_eq!(contract.balance_of(accounts.bob), 10_510);
+        }
+
+        #[ink::test]
+        fn sweep_dust_only_sweeps_small_balances() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &5);
+            contract.balances.insert(accounts.charlie, &500);
+            contract.balances.insert(accounts.django, &9);
+            let swept_accounts = ink::prelude::vec![
+                accounts.bob,
+                accounts.charlie,
+                accounts.django,
+                accounts.eve
+            ];
+
+            // Act
+            let swept = contract.sweep_dust(swept_accounts, 10);
+
+            // Assert
+            assert_eq!(swept, Ok(14));
+            assert_eq!(contract.balance_of(accounts.alice), 14);
+            assert!(!contract.has_account(accounts.bob));
+            assert!(!contract.has_account(accounts.django));
+            assert_eq!(contract.balance_of(accounts.charlie), 500);
+            let closed = recorded_events()
+                .into_iter()
+                .filter(|event| matches!(event, Event::AccountClosed(_)))
+                .count();
+            assert_eq!(closed, 2);
+        }
+
+        #[ink::test]
+        fn sweep_dust_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.charlie, &5);
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.sweep_dust(ink::prelude::vec![accounts.charlie], 10);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert_eq!(contract.balance_of(accounts.charlie), 5);
+        }
+
+        #[ink::test]
+        fn deposit_ref_rejects_reused_reference() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let reference = [0x11; 32];
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+
+            // Act
+            let first = contract.deposit_ref(reference);
+            let second = contract.deposit_ref(reference);
+            let other = contract.deposit_ref([0x12; 32]);
+
+            // Assert
+            assert_eq!(first, Ok(()));
+            assert_eq!(second, Err(ContractError::DuplicateReference));
+            assert_eq!(other, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 200);
+        }
+
+        fn withdraw_with_fee(bps: u16, withdrawal_amount: Balance) -> (Balance, Balance) {
+            let (mut contract, accounts) = init();
+            let owner = accounts.alice;
+            let caller = accounts.bob;
+            let recipient = accounts.django;
+            contract.set_fee_bps(bps).unwrap();
+            contract.balances.insert(caller, &withdrawal_amount);
+            contract.total_deposited = withdrawal_amount;
+            let recipient_chain_balance = get_chain_balance(recipient);
+            set_caller(caller);
+
+            contract
+                .withdraw_to(recipient, Some(withdrawal_amount))
+                .unwrap();
+
+            (
+                get_chain_balance(recipient) - recipient_chain_balance,
+                contract.balances.get(owner).unwrap_or(0),
+            )
+        }
+
+        #[ink::test]
+        fn withdraw_without_fee_works() {
+            assert_eq!(withdraw_with_fee(0, 1000), (1000, 0));
+        }
+
+        #[ink::test]
+        fn withdraw_with_fee_works() {
+            assert_eq!(withdraw_with_fee(250, 1000), (975, 25));
+        }
+
+        #[ink::test]
+        fn withdraw_with_full_fee_works() {
+            assert_eq!(withdraw_with_fee(10_000, 1000), (0, 1000));
+        }
+
+        #[ink::test]
+        fn owner_withdraw_sweeps_accrued_fees() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let owner = accounts.alice;
+            let recipient = accounts.django;
+            contract.set_fee_bps(250).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.withdraw(None).unwrap();
+            let recipient_chain_balance = get_chain_balance(recipient);
+            set_caller(owner);
+
+            // Act
+            let swept = contract.owner_withdraw(recipient).unwrap();
+
+            // Assert
+            assert_eq!(swept, 25);
+            assert_eq!(contract.balance_of(owner), 0);
+            assert_eq!(get_chain_balance(recipient), recipient_chain_balance + 25);
+            assert_eq!(contract.total_deposited(), 0);
+        }
+
+        #[ink::test]
+        fn owner_withdraw_fails_without_balance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+
+            // Act
+            let result = contract.owner_withdraw(accounts.django);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::AccountWithoutBalance));
+        }
+
+        #[ink::test]
+        fn owner_withdraw_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.owner_withdraw(accounts.bob);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn set_fee_bps_fails_above_maximum() {
+            // Arrange
+            let (mut contract, _) = init();
+
+            // Act
+            let result = contract.set_fee_bps(10_001);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InvalidFee));
+            assert_eq!(contract.fee_bps(), 0);
+        }
+
+        #[ink::test]
+        fn audit_log_records_deposits_and_withdrawals() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_audit_enabled(true).unwrap();
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            set_transferred_value(500);
+            contract.deposit().unwrap();
+            set_block_timestamp(2_000);
+
+            // Act
+            contract.withdraw(Some(200)).unwrap();
+
+            // Assert
+            assert_eq!(
+                contract.recent_activity(10),
+                vec![
+                    (AUDIT_WITHDRAW, accounts.bob, 200, 2_000),
+                    (AUDIT_DEPOSIT, accounts.bob, 500, 1_000),
+                ]
+            );
+            assert_eq!(contract.recent_activity(1).len(), 1);
+        }
+
+        #[ink::test]
+        fn audit_log_is_empty_unless_enabled() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+
+            // Act
+            contract.deposit().unwrap();
+
+            // Assert
+            assert!(contract.recent_activity(10).is_empty());
+        }
+
+        #[ink::test]
+        fn audit_log_keeps_only_latest_entries() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_audit_enabled(true).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            for amount in 1..=AUDIT_LOG_SIZE as Balance + 5 {
+                set_transferred_value(amount);
+                contract.deposit().unwrap();
+            }
+
+            // Assert
+            let activity = contract.recent_activity(u32::MAX);
+            assert_eq!(activity.len(), AUDIT_LOG_SIZE);
+            assert_eq!(activity[0].2, AUDIT_LOG_SIZE as Balance + 5);
+            assert_eq!(activity[AUDIT_LOG_SIZE - 1].2, 6);
+        }
+
+        #[ink::test]
+        fn deposit_fails_for_new_account_above_account_limit() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_accounts(2).unwrap();
+            for account in [accounts.bob, accounts.charlie] {
+                set_caller(account);
+                set_transferred_value(100);
+                contract.deposit().unwrap();
+            }
+            set_caller(accounts.django);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::AccountLimitReached));
+            assert_eq!(contract.account_count(), 2);
+            set_caller(accounts.bob);
+            assert_eq!(contract.deposit(), Ok(200));
+        }
+
+        #[ink::test]
+        fn account_count_drops_when_account_closes() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+            contract.deposit().unwrap();
+
+            // Act
+            contract.withdraw(None).unwrap();
+
+            // Assert
+            assert_eq!(contract.account_count(), 0);
+        }
+
+        #[ink::test]
+        fn forfeit_moves_part_of_balance_to_treasury() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.forfeit(Some(300));
+
+            // Assert
+            assert_eq!(result, Ok(300));
+            assert_eq!(contract.balance_of(accounts.bob), 700);
+            assert_eq!(contract.treasury(), 300);
+            assert_eq!(contract.total_deposited(), 1000);
+            match recorded_events().last() {
+                Some(Event::Forfeited(event)) => {
+                    assert_eq!(event.from, accounts.bob);
+                    assert_eq!(event.amount, 300);
+                }
+                _ => panic!("expected a Forfeited event"),
+            }
+        }
+
+        #[ink::test]
+        fn forfeit_without_amount_gives_up_whole_balance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.forfeit(None);
+
+            // Assert
+            assert_eq!(result, Ok(1000));
+            assert!(!contract.has_account(accounts.bob));
+            assert_eq!(contract.treasury(), 1000);
+        }
+
+        #[ink::test]
+        fn deposit_and_withdraw_emit_events_by_default() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+
+            // Act
+            contract.deposit().unwrap();
+            contract.withdraw(Some(200)).unwrap();
+
+            // Assert
+            let events = recorded_events();
+            assert!(matches!(events[events.len() - 2], Event::Deposited(_)));
+            assert!(matches!(events[events.len() - 1], Event::Withdrawn(_)));
+        }
+
+        #[ink::test]
+        fn deposit_and_withdraw_skip_events_when_disabled() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_emit_events(false).unwrap();
+            let emitted = recorded_events().len();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+
+            // Act
+            contract.deposit().unwrap();
+            contract.withdraw(None).unwrap();
+
+            // Assert
+            assert_eq!(recorded_events().len(), emitted);
+            assert!(!contract.config().emit_events);
+        }
+
+        #[ink::test]
+        fn settle_pays_escrow_to_payee() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            let payee_balance = get_chain_balance(accounts.charlie);
+
+            // Act
+            let result = contract.settle(id, None);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(get_chain_balance(accounts.charlie), payee_balance + 400);
+            assert!(contract.escrow_of(id).unwrap().released);
+            assert_eq!(contract.total_deposited(), 0);
+            assert_eq!(
+                contract.settle(id, None),
+                Err(ContractError::EscrowAlreadyResolved)
+            );
+        }
+
+        #[ink::test]
+        fn settle_fails_for_frozen_payer() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            set_caller(accounts.alice);
+            contract.freeze(accounts.bob).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.settle(id, None);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::AccountFrozen));
+            assert!(!contract.escrow_of(id).unwrap().released);
+            assert_eq!(contract.total_deposited(), 400);
+        }
+
+        #[ink::test]
+        fn refund_escrow_returns_funds_to_payer() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            let payer_balance = get_chain_balance(accounts.bob);
+
+            // Act
+            let result = contract.refund_escrow(id);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(get_chain_balance(accounts.bob), payer_balance + 400);
+            assert_eq!(
+                contract.refund_escrow(id),
+                Err(ContractError::EscrowAlreadyResolved)
+            );
+            assert_eq!(
+                contract.settle(id, None),
+                Err(ContractError::EscrowAlreadyResolved)
+            );
+        }
+
+        #[ink::test]
+        fn settle_fails_for_non_payer() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            set_caller(accounts.charlie);
+
+            // Act
+            let result = contract.settle(id, None);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotPayer));
+            assert!(!contract.escrow_of(id).unwrap().released);
+        }
+
+        #[ink::test]
+        fn arbitrate_pays_disputed_escrow_to_chosen_party() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            set_caller(accounts.charlie);
+            contract.dispute(id).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.refund_escrow(id),
+                Err(ContractError::EscrowDisputed)
+            );
+            let payer_balance = get_chain_balance(accounts.bob);
+            set_caller(accounts.eve);
+
+            // Act
+            let result = contract.arbitrate(id, false);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(get_chain_balance(accounts.bob), payer_balance + 400);
+            assert!(contract.escrow_of(id).unwrap().released);
+        }
+
+        #[ink::test]
+        fn arbitrate_fails_for_non_arbiter() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            contract.dispute(id).unwrap();
+            set_caller(accounts.charlie);
+
+            // Act
+            let result = contract.arbitrate(id, true);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotArbiter));
+            assert!(!contract.escrow_of(id).unwrap().released);
+        }
+
+        #[ink::test]
+        fn arbitrate_fails_without_dispute() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            set_caller(accounts.eve);
+
+            // Act
+            let result = contract.arbitrate(id, true);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotDisputed));
+            assert_eq!(contract.dispute(id), Err(ContractError::NotEscrowParty));
+        }
+
+        #[ink::test]
+        fn claim_expired_fails_before_deadline() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            set_caller(accounts.django);
+            set_block_timestamp(10_000);
+
+            // Act
+            let result = contract.claim_expired(id);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::EscrowNotExpired));
+            assert!(!contract.escrow_of(id).unwrap().released);
+        }
+
+        #[ink::test]
+        fn claim_expired_refunds_payer_after_deadline() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            let payer_balance = get_chain_balance(accounts.bob);
+            set_caller(accounts.django);
+            set_block_timestamp(10_001);
+
+            // Act
+            let result = contract.claim_expired(id);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(get_chain_balance(accounts.bob), payer_balance + 400);
+            assert_eq!(
+                contract.claim_expired(id),
+                Err(ContractError::EscrowAlreadyResolved)
+            );
+        }
+
+        #[ink::test]
+        fn settle_releases_escrow_in_milestones() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            let payee_balance = get_chain_balance(accounts.charlie);
+
+            // Act
+            contract.settle(id, Some(150)).unwrap();
+            let first = contract.escrow_of(id).unwrap();
+            contract.settle(id, Some(250)).unwrap();
+            let second = contract.escrow_of(id).unwrap();
+
+            // Assert
+            assert_eq!(first.released_amount, 150);
+            assert!(!first.released);
+            assert_eq!(second.released_amount, 400);
+            assert!(second.released);
+            assert_eq!(get_chain_balance(accounts.charlie), payee_balance + 400);
+            assert_eq!(contract.total_deposited(), 0);
+        }
+
+        #[ink::test]
+        fn settle_fails_above_remaining_escrow() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(400);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.eve, 10_000)
+                .unwrap();
+            contract.settle(id, Some(300)).unwrap();
+
+            // Act
+            let result = contract.settle(id, Some(101));
+
+            // Assert
+            assert_eq!(
+                result,
+                Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance)
+            );
+            assert_eq!(contract.escrow_of(id).unwrap().released_amount, 300);
+            assert_eq!(contract.total_deposited(), 100);
+        }
+
+        #[ink::test]
+        fn stats_reflects_deposits() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            for account in [accounts.bob, accounts.charlie] {
+                set_caller(account);
+                transfer_in(300);
+                contract.deposit().unwrap();
+            }
+
+            // Act
+            let stats = contract.stats();
+
+            // Assert
+            assert_eq!(
+                stats,
+                Stats {
+                    total_deposited: 600,
+                    account_count: 2,
+                    contract_balance: contract.contract_balance(),
+                    paused: false,
+                    owner: accounts.alice,
+                }
+            );
+            assert_eq!(stats.contract_balance, get_chain_balance(accounts.alice));
+        }
+
+        #[ink::test]
+        fn lifetime_deposited_survives_withdrawals() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(300);
+            contract.deposit().unwrap();
+            contract.withdraw(None).unwrap();
+            set_transferred_value(200);
+
+            // Act
+            contract.deposit().unwrap();
+
+            // Assert
+            assert_eq!(contract.lifetime_deposited_of(accounts.bob), 500);
+            assert_eq!(contract.balance_of(accounts.bob), 200);
+        }
+
+        #[ink::test]
+        fn withdraw_with_vesting_pays_immediate_part_and_streams_rest() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            let chain_balance = get_chain_balance(accounts.bob);
+
+            // Act
+            contract.withdraw_with_vesting(1000, 2_500, 4_000).unwrap();
+
+            // Assert
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 250);
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.stream_of(accounts.bob).unwrap().total, 750);
+            assert_eq!(contract.total_deposited(), 750);
+        }
+
+        #[ink::test]
+        fn withdraw_with_vesting_applies_withdrawal_checks_to_full_amount() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(1000).unwrap();
+            contract.set_daily_limit(100).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.preauthorize(50).unwrap();
+
+            // Act
+            let over_limit = contract.withdraw_with_vesting(1000, 0, 0);
+            let vested = contract.withdraw_with_vesting(1000, 0, 4_000);
+            let over_preauth = contract.withdraw_with_vesting(80, 0, 4_000);
+
+            // Assert
+            assert_eq!(over_limit, Err(ContractError::DailyLimitExceeded));
+            assert_eq!(vested, Err(ContractError::DailyLimitExceeded));
+            assert_eq!(over_preauth, Err(ContractError::ExceedsPreauthorization));
+            assert_eq!(contract.stream_of(accounts.bob), None);
+            assert_eq!(contract.claim_stream(), Err(ContractError::NoStream));
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn withdraw_with_vesting_charges_fee_and_limit_on_full_amount() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(1000).unwrap();
+            contract.set_daily_limit(2000).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            let chain_balance = get_chain_balance(accounts.bob);
+
+            // Act
+            contract.withdraw_with_vesting(1000, 5_000, 4_000).unwrap();
+
+            // Assert
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 450);
+            assert_eq!(contract.stream_of(accounts.bob).unwrap().total, 450);
+            assert_eq!(contract.balance_of(accounts.alice), 100);
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.remaining_daily_limit(), 1000);
+            assert_eq!(contract.total_deposited(), 550);
+        }
+
+        #[ink::test]
+        fn withdraw_with_vesting_pays_all_now_without_duration() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            let chain_balance = get_chain_balance(accounts.bob);
+
+            // Act
+            contract.withdraw_with_vesting(1000, 2_500, 0).unwrap();
+
+            // Assert
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 1000);
+            assert_eq!(contract.stream_of(accounts.bob), None);
+            assert!(!contract.has_account(accounts.bob));
+        }
+
+        #[ink::test]
+        fn vested_remainder_is_claimable_partway_and_fully() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            contract.withdraw_with_vesting(1000, 2_500, 4_000).unwrap();
+            set_block_timestamp(2_000);
+
+            // Act
+            let partial = contract.claim_stream();
+            set_block_timestamp(5_000);
+            let rest = contract.claim_stream();
+
+            // Assert
+            assert_eq!(partial, Ok(187));
+            assert_eq!(rest, Ok(563));
+            assert_eq!(contract.stream_of(accounts.bob), None);
+            assert_eq!(contract.total_deposited(), 0);
+        }
+
+        #[ink::test]
+        fn withdraw_fails_when_total_deposited_would_underflow() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 100;
+            set_caller(accounts.bob);
+            let chain_balance = get_chain_balance(accounts.bob);
+
+            // Act
+            let result = contract.withdraw(Some(500));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::Insolvent));
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+            assert_eq!(contract.total_deposited(), 100);
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance);
+        }
+
+        #[ink::test]
+        fn available_to_withdraw_is_full_balance_when_unrestricted() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            set_caller(accounts.bob);
+
+            // Act
+            let available = contract.available_to_withdraw();
+
+            // Assert
+            assert_eq!(available, 1000);
+        }
+
+        #[ink::test]
+        fn available_to_withdraw_counts_pending_interest_up_to_preauthorization() {
+            // Arrange
+            let (mut contract, _) = init_with_interest(10, 1_000);
+            set_block_timestamp(2 * MILLIS_PER_DAY);
+
+            // Act
+            let available = contract.available_to_withdraw();
+
+            // Assert
+            assert_eq!(available, 10_020);
+            contract.preauthorize(400).unwrap();
+            assert_eq!(contract.available_to_withdraw(), 400);
+        }
+
+        #[ink::test]
+        fn available_to_withdraw_is_zero_while_locked() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            set_transferred_value(1000);
+            contract.deposit_locked(5_000).unwrap();
+
+            // Act
+            let available = contract.available_to_withdraw();
+
+            // Assert
+            assert_eq!(available, 0);
+            set_block_timestamp(5_000);
+            assert_eq!(contract.available_to_withdraw(), 1000);
+        }
+
+        #[ink::test]
+        fn available_to_withdraw_respects_daily_limit_and_cooldown() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_daily_limit(500).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.withdraw(Some(200)).unwrap();
+
+            // Act
+            let available = contract.available_to_withdraw();
+
+            // Assert
+            assert_eq!(available, 300);
+            set_caller(accounts.alice);
+            contract.set_withdraw_cooldown(10_000).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(contract.available_to_withdraw(), 0);
+        }
+
+        #[ink::test]
+        fn new_records_deployer_as_owner() {
+            // Arrange
+            let accounts = get_default_accounts();
+            set_caller(accounts.bob);
+
+            // Act
+            let contract = Workshop::new();
+
+            // Assert
+            assert_eq!(contract.owner(), accounts.bob);
+        }
+
+        #[ink::test]
+        fn new_with_owner_assigns_given_owner() {
+            // Arrange
+            let accounts = get_default_accounts();
+            set_caller(accounts.alice);
+
+            // Act
+            let mut contract = Workshop::new_with_owner(accounts.bob);
+
+            // Assert
+            assert_eq!(contract.owner(), accounts.bob);
+            assert_eq!(contract.pause(), Err(ContractError::NotOwner));
+            set_caller(accounts.bob);
+            assert_eq!(contract.pause(), Ok(()));
+        }
+
+        #[ink::test]
+        fn deposit_ignores_whitelist_when_disabled() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Ok(100));
+            assert!(!contract.is_whitelisted(accounts.bob));
+        }
+
+        #[ink::test]
+        fn deposit_requires_whitelist_when_enabled() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_whitelist_enabled(true).unwrap();
+            contract.add_to_whitelist(accounts.bob).unwrap();
+            set_caller(accounts.charlie);
+            set_transferred_value(100);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotWhitelisted));
+            assert_eq!(
+                contract.deposit_for(accounts.django),
+                Err(ContractError::NotWhitelisted)
+            );
+            assert_eq!(contract.deposit_for(accounts.bob), Ok(()));
+        }
+
+        #[ink::test]
+        fn withdraw_works_after_removal_from_whitelist() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_whitelist_enabled(true).unwrap();
+            contract.add_to_whitelist(accounts.bob).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+            contract.deposit().unwrap();
+            set_caller(accounts.alice);
+            contract.remove_from_whitelist(accounts.bob).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Ok(0));
+            assert!(!contract.is_whitelisted(accounts.bob));
+        }
+
+        #[ink::test]
+        fn accounts_page_lists_accounts_in_chunks() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            for (account, amount) in [
+                (accounts.bob, 100),
+                (accounts.charlie, 200),
+                (accounts.django, 300),
+            ] {
+                set_caller(account);
+                set_transferred_value(amount);
+                contract.deposit().unwrap();
+            }
+
+            // Act
+            let first = contract.accounts_page(0, 2);
+            let second = contract.accounts_page(2, 2);
+
+            // Assert
+            assert_eq!(first, vec![(accounts.bob, 100), (accounts.charlie, 200)]);
+            assert_eq!(second, vec![(accounts.django, 300)]);
+            assert!(contract.accounts_page(3, 2).is_empty());
+        }
+
+        #[ink::test]
+        fn accounts_page_drops_closed_accounts() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            for account in [accounts.bob, accounts.charlie, accounts.django] {
+                set_caller(account);
+                set_transferred_value(100);
+                contract.deposit().unwrap();
+            }
+            set_caller(accounts.bob);
+
+            // Act
+            contract.withdraw(None).unwrap();
+
+            // Assert
+            assert_eq!(contract.account_count(), 2);
+            assert_eq!(
+                contract.accounts_page(0, 10),
+                vec![(accounts.django, 100), (accounts.charlie, 100)]
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_to_allows_any_destination_without_strict_mode() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.withdraw_to(accounts.django, Some(300));
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 700);
+        }
+
+        #[ink::test]
+        fn withdraw_to_rejects_untrusted_destination_in_strict_mode() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.set_strict_withdraw(true).unwrap();
+            contract.add_trusted_destination(accounts.charlie).unwrap();
+
+            // Act
+            let result = contract.withdraw_to(accounts.django, Some(300));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::UntrustedDestination));
+            assert_eq!(contract.withdraw_to(accounts.charlie, Some(300)), Ok(()));
+            assert_eq!(contract.withdraw(Some(100)), Ok(600));
+        }
+
+        #[ink::test]
+        fn transfer_all_rejects_untrusted_destination_in_strict_mode() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            set_caller(accounts.bob);
+            contract.set_strict_withdraw(true).unwrap();
+            contract.add_trusted_destination(accounts.charlie).unwrap();
+
+            // Act
+            let result = contract.transfer_all(accounts.django);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::UntrustedDestination));
+            assert_eq!(contract.balance_of(accounts.django), 0);
+            assert_eq!(contract.transfer_all(accounts.charlie), Ok(1000));
+        }
+
+        #[ink::test]
+        fn removed_trusted_destination_is_rejected() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.set_strict_withdraw(true).unwrap();
+            contract.add_trusted_destination(accounts.charlie).unwrap();
+            contract
+                .remove_trusted_destination(accounts.charlie)
+                .unwrap();
+
+            // Act
+            let result = contract.withdraw_to(accounts.charlie, Some(300));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::UntrustedDestination));
+            assert!(!contract.is_trusted_destination(accounts.bob, accounts.charlie));
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn reentrant_withdraw_cannot_double_spend() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            transfer_in(1000);
+            contract.deposit().unwrap();
+            let chain_balance = get_chain_balance(accounts.bob);
+            mock_recipient::arm(accounts.bob, 1000);
+
+            // Act
+            let result = contract.withdraw(Some(1000));
+
+            // Assert
+            assert_eq!(result, Ok(0));
+            assert_eq!(
+                mock_recipient::reentry_result(),
+                Some(Err(ContractError::ReentrancyDetected))
+            );
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 1000);
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.total_deposited(), 0);
+        }
+
+        #[ink::test]
+        fn withdraw_debits_balance_before_paying_recipient() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.balances.insert(accounts.charlie, &1000);
+            contract.total_deposited = 2000;
+            set_caller(accounts.bob);
+            mock_recipient::arm(accounts.charlie, 1);
+
+            // Act
+            let result = contract.withdraw_to(accounts.charlie, Some(400));
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(
+                mock_recipient::reentry_result(),
+                Some(Err(ContractError::ReentrancyDetected))
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+            assert_eq!(contract.balance_of(accounts.charlie), 1000);
+        }
+
+        #[ink::test]
+        fn withdraw_keep_alive_works_above_existential_deposit() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_existential_deposit(100).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.withdraw_keep_alive(Some(100));
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 900);
+        }
+
+        #[ink::test]
+        fn withdraw_keep_alive_fails_below_existential_deposit() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_existential_deposit(100).unwrap();
+            contract.set_fee_bps(1_000).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.withdraw_keep_alive(Some(105));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::BelowExistentialDeposit));
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn balances_of_returns_balances_in_input_order() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &100);
+            contract.balances.insert(accounts.django, &300);
+
+            // Act
+            let balances =
+                contract.balances_of(vec![accounts.django, accounts.charlie, accounts.bob]);
+
+            // Assert
+            assert_eq!(balances, Ok(vec![300, 0, 100]));
+        }
+
+        #[ink::test]
+        fn balances_of_fails_above_batch_cap() {
+            // Arrange
+            let (contract, accounts) = init();
+
+            // Act
+            let result = contract.balances_of(vec![accounts.bob; MAX_BATCH_QUERY + 1]);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::TooManyAccounts));
+        }
+
+        #[ink::test]
+        fn withdrawal_fee_is_split_between_owner_and_pool() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(1_000).unwrap();
+            contract.set_fee_to_pool_bps(4_000).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+
+            // Act
+            contract.withdraw(Some(1000)).unwrap();
+
+            // Assert
+            assert_eq!(contract.reward_pool(), 40);
+            assert_eq!(contract.balance_of(accounts.alice), 60);
+            assert_eq!(contract.total_deposited(), 100);
+        }
+
+        #[ink::test]
+        fn distribute_rewards_credits_holders_pro_rata() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            for (account, amount) in [(accounts.bob, 300), (accounts.charlie, 100)] {
+                set_caller(account);
+                set_transferred_value(amount);
+                contract.deposit().unwrap();
+            }
+            set_caller(accounts.alice);
+            set_transferred_value(100);
+            contract.fund_reward_pool().unwrap();
+
+            // Act
+            let result = contract.distribute_rewards();
+
+            // Assert
+            assert_eq!(result, Ok(100));
+            assert_eq!(contract.balance_of(accounts.bob), 375);
+            assert_eq!(contract.balance_of(accounts.charlie), 125);
+            assert_eq!(contract.reward_pool(), 0);
+            assert_eq!(contract.total_deposited(), 500);
+        }
+
+        #[ink::test]
+        fn transfer_all_moves_whole_balance_and_closes_account() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &700);
+            contract.balances.insert(accounts.charlie, &100);
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.transfer_all(accounts.charlie);
+
+            // Assert
+            assert_eq!(result, Ok(700));
+            assert_eq!(contract.balance_of(accounts.charlie), 800);
+            assert!(!contract.has_account(accounts.bob));
+            match recorded_events().last() {
+                Some(Event::AccountClosed(event)) => assert_eq!(event.account, accounts.bob),
+                _ => panic!("expected an AccountClosed event"),
+            }
+        }
+
+        #[ink::test]
+        fn transfer_all_fails_for_self_or_empty_account() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &700);
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.transfer_all(accounts.bob);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::SelfTransfer));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_all(accounts.bob),
+                Err(ContractError::AccountWithoutBalance)
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_as_operator_pays_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.set_operator(accounts.charlie, true).unwrap();
+            let owner_chain_balance = get_chain_balance(accounts.bob);
+            let operator_chain_balance = get_chain_balance(accounts.charlie);
+            set_caller(accounts.charlie);
+
+            // Act
+            let result = contract.withdraw_as_operator(accounts.bob, 400);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert!(contract.is_operator(accounts.bob, accounts.charlie));
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+            assert_eq!(get_chain_balance(accounts.bob), owner_chain_balance + 400);
+            assert_eq!(get_chain_balance(accounts.charlie), operator_chain_balance);
+        }
+
+        #[ink::test]
+        fn withdraw_as_operator_fails_without_approval() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.set_operator(accounts.charlie, true).unwrap();
+            contract.set_operator(accounts.charlie, false).unwrap();
+            set_caller(accounts.charlie);
+
+            // Act
+            let result = contract.withdraw_as_operator(accounts.bob, 400);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOperator));
+            set_caller(accounts.django);
+            assert_eq!(
+                contract.withdraw_as_operator(accounts.bob, 400),
+                Err(ContractError::NotOperator)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn refund_recent_skips_fee_inside_window() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(1_000).unwrap();
+            contract.set_refund_window(60_000).unwrap();
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            transfer_in(500);
+            contract.deposit().unwrap();
+            let chain_balance = get_chain_balance(accounts.bob);
+            set_block_timestamp(60_999);
+
+            // Act
+            let result = contract.refund_recent(500);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 500);
+            assert_eq!(contract.balance_of(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn refund_recent_charges_fee_after_window() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(1_000).unwrap();
+            contract.set_refund_window(60_000).unwrap();
+            set_caller(accounts.bob);
+            set_block_timestamp(1_000);
+            transfer_in(500);
+            contract.deposit().unwrap();
+            let chain_balance = get_chain_balance(accounts.bob);
+            set_block_timestamp(61_000);
+
+            // Act
+            let result = contract.refund_recent(500);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 450);
+            assert_eq!(contract.balance_of(accounts.alice), 50);
+        }
+
+        #[ink::test]
+        fn deposit_split_rejects_overflowing_shares() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.alice);
+            set_transferred_value(1);
+
+            // Act
+            let result =
+                contract.deposit_split(vec![(accounts.bob, Balance::MAX), (accounts.charlie, 2)]);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::BalanceOverflow));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.balance_of(accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn set_code_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.set_code(Hash::from([0x01; 32]));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert!(!recorded_events()
+                .into_iter()
+                .any(|event| matches!(event, Event::CodeUpgraded(_))));
+        }
+
+        #[ink::test]
+        fn upgrade_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.upgrade(Hash::from([0x01; 32]));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert_eq!(contract.schema_version(), VERSION);
+            assert!(!contract.migration_pending());
+        }
+
+        #[ink::test]
+        fn migrate_runs_once_after_upgrade() {
+            // Arrange
+            // The off-chain environment cannot swap code, so stand in for `upgrade`.
+            let (mut contract, _) = init();
+            contract.schema_version += 1;
+            contract.migration_pending = true;
+
+            // Act
+            let result = contract.migrate();
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.schema_version(), VERSION + 1);
+            assert_eq!(contract.migrate(), Err(ContractError::NoMigrationPending));
+        }
+
+        #[ink::test]
+        fn migrate_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.migration_pending = true;
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.migrate();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert!(contract.migration_pending());
+        }
+
+        #[ink::test]
+        fn withdraw_within_preauthorization_draws_it_down() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.preauthorize(500).unwrap();
+
+            // Act
+            let result = contract.withdraw(Some(300));
+
+            // Assert
+            assert_eq!(result, Ok(700));
+            assert_eq!(contract.preauthorization_of(accounts.bob), Some(200));
+        }
+
+        #[ink::test]
+        fn transfers_draw_down_preauthorization() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            set_caller(accounts.bob);
+            contract.preauthorize(500).unwrap();
+            contract.approve(accounts.charlie, 1000).unwrap();
+            contract.transfer(accounts.django, 300).unwrap();
+            set_caller(accounts.charlie);
+
+            // Act
+            let result = contract.transfer_from(accounts.bob, accounts.charlie, 201);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::ExceedsPreauthorization));
+            assert_eq!(contract.preauthorization_of(accounts.bob), Some(200));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_all(accounts.django),
+                Err(ContractError::ExceedsPreauthorization)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 700);
+        }
+
+        #[ink::test]
+        fn withdraw_beyond_preauthorization_fails() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            contract.preauthorize(500).unwrap();
+            contract.withdraw(Some(300)).unwrap();
+
+            // Act
+            let result = contract.withdraw(Some(201));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::ExceedsPreauthorization));
+            assert_eq!(contract.balance_of(accounts.bob), 700);
+            assert_eq!(contract.preauthorization_of(accounts.bob), Some(200));
+        }
+
+        #[ink::test]
+        fn reconcile_surplus_sends_only_unaccounted_funds() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_chain_balance(accounts.alice, 1500);
+            let recipient_balance = get_chain_balance(accounts.django);
+
+            // Act
+            let result = contract.reconcile_surplus(accounts.django);
+
+            // Assert
+            assert_eq!(result, Ok(500));
+            assert_eq!(get_chain_balance(accounts.django), recipient_balance + 500);
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+            assert_eq!(contract.contract_balance(), 1000);
+            assert!(contract.solvency());
+        }
+
+        #[ink::test]
+        fn reconcile_surplus_fails_without_surplus() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.total_deposited = 1000;
+            set_chain_balance(accounts.alice, 1000);
+
+            // Act
+            let result = contract.reconcile_surplus(accounts.django);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NoSurplus));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.reconcile_surplus(accounts.bob),
+                Err(ContractError::NotOwner)
+            );
+        }
+
+        #[ink::test]
+        fn fee_tiers_pick_bps_for_each_band() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(50).unwrap();
+            contract
+                .set_fee_tiers(vec![(100, 100), (1000, 200), (5000, 300)])
+                .unwrap();
+
+            // Act
+            let fees: Vec<Balance> = [50, 500, 1000, 4999, 6000]
+                .into_iter()
+                .map(|amount| contract.withdrawal_fee(accounts.bob, amount).unwrap())
+                .collect();
+
+            // Assert
+            assert_eq!(fees, vec![0, 5, 20, 99, 180]);
+            assert_eq!(contract.config().fee_tiers.len(), 3);
+        }
+
+        #[ink::test]
+        fn withdraw_charges_tiered_fee() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_tiers(vec![(0, 100), (1000, 200)]).unwrap();
+            contract.balances.insert(accounts.bob, &2000);
+            contract.total_deposited = 2000;
+            set_caller(accounts.bob);
+
+            // Act
+            contract.withdraw(Some(1500)).unwrap();
+
+            // Assert
+            assert_eq!(contract.balance_of(accounts.alice), 30);
+        }
+
+        #[ink::test]
+        fn set_fee_tiers_rejects_invalid_schedules() {
+            // Arrange
+            let (mut contract, _) = init();
+
+            // Act
+            let unordered = contract.set_fee_tiers(vec![(1000, 100), (1000, 200)]);
+            let too_high = contract.set_fee_tiers(vec![(0, 10_001)]);
+
+            // Assert
+            assert_eq!(unordered, Err(ContractError::InvalidFee));
+            assert_eq!(too_high, Err(ContractError::InvalidFee));
+            assert!(contract.fee_tiers().is_empty());
+        }
+
+        #[ink::test]
+        fn deposit_quiet_credits_without_events() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            let emitted = recorded_events().len();
+            set_caller(accounts.bob);
+            set_transferred_value(400);
+
+            // Act
+            let result = contract.deposit_quiet();
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 400);
+            assert_eq!(contract.total_deposited(), 400);
+            assert_eq!(recorded_events().len(), emitted);
+        }
+
+        #[ink::test]
+        fn deposit_quiet_emits_no_interest_or_bonus_events() {
+            // Arrange
+            let (mut contract, accounts) = init_with_interest(10, 1_000);
+            set_caller(accounts.alice);
+            contract.set_first_deposit_bonus(50).unwrap();
+            set_transferred_value(500);
+            contract.fund_promo_pool().unwrap();
+            set_block_timestamp(3 * MILLIS_PER_DAY);
+            let emitted = recorded_events().len();
+
+            // Act
+            set_caller(accounts.bob);
+            set_transferred_value(400);
+            let interest_result = contract.deposit_quiet();
+            set_caller(accounts.charlie);
+            let bonus_result = contract.deposit_quiet();
+
+            // Assert
+            assert_eq!(interest_result, Ok(()));
+            assert_eq!(bonus_result, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 10_430);
+            assert_eq!(contract.balance_of(accounts.charlie), 450);
+            assert_eq!(recorded_events().len(), emitted);
+        }
+
+        #[ink::test]
+        fn terminate_fails_while_user_funds_remain() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            contract.pause().unwrap();
+
+            // Act
+            let result = contract.terminate();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::FundsStillHeld));
+        }
+
+        #[ink::test]
+        fn terminate_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.pause().unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.terminate();
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+        }
+
+        #[ink::test]
+        fn terminate_pays_remaining_balance_to_owner() {
+            // Arrange
+            let contract_id = AccountId::from([0x09; 32]);
+            set_callee(contract_id);
+            let (mut contract, accounts) = init();
+            set_chain_balance(contract_id, 500);
+            contract.balances.insert(accounts.alice, &200);
+            contract.total_deposited = 200;
+            contract.pause().unwrap();
+
+            // Act
+            let terminate = move || {
+                let _ = contract.terminate();
+            };
+
+            // Assert
+            ink::env::test::assert_contract_termination::<ink::env::DefaultEnvironment, _>(
+                terminate,
+                accounts.alice,
+                500,
+            );
+        }
+
+        #[ink::test]
+        fn operation_counts_track_deposits_and_withdrawals() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            for _ in 0..3 {
+                set_transferred_value(100);
+                contract.deposit().unwrap();
+            }
+            contract.withdraw(Some(50)).unwrap();
+            contract.withdraw(None).unwrap();
+
+            // Assert
+            assert_eq!(contract.operation_counts(), (3, 2));
+        }
+
+        #[ink::test]
+        fn operation_counts_saturate() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.deposit_count = u64::MAX;
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+
+            // Act
+            contract.deposit().unwrap();
+
+            // Assert
+            assert_eq!(contract.operation_counts(), (u64::MAX, 0));
+        }
+
+        // The off-chain environment cannot dispatch cross-contract calls, so these cover the
+        // checks made before the PSP22 token is called.
+        #[ink::test]
+        fn deposit_token_fails_without_token() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.deposit_token(100);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::TokenNotSet));
+            assert_eq!(contract.token_balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn deposit_token_requires_whitelist_when_enabled() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_token(Some(accounts.eve)).unwrap();
+            contract.set_whitelist_enabled(true).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.deposit_token(100);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotWhitelisted));
+            assert_eq!(contract.token_balance_of(accounts.bob), 0);
+            assert!(mock_token::transfers().is_empty());
+        }
+
+        #[ink::test]
+        fn withdraw_token_fails_above_token_balance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_token(Some(accounts.eve)).unwrap();
+            contract.token_balances.insert(accounts.bob, &100);
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.withdraw_token(101);
+
+            // Assert
+            assert_eq!(
+                result,
+                Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance)
+            );
+            assert_eq!(contract.token_balance_of(accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn deposit_and_withdraw_token_work() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_token(Some(accounts.eve)).unwrap();
+            set_caller(accounts.bob);
+            let contract_id = contract_id();
+
+            // Act
+            let deposited = contract.deposit_token(300);
+            let withdrawn = contract.withdraw_token(100);
+
+            // Assert
+            assert_eq!(deposited, Ok(300));
+            assert_eq!(withdrawn, Ok(200));
+            assert_eq!(contract.token_balance_of(accounts.bob), 200);
+            assert_eq!(
+                mock_token::transfers(),
+                vec![
+                    (accounts.eve, accounts.bob, contract_id, 300),
+                    (accounts.eve, contract_id, accounts.bob, 100),
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn token_balance_is_restored_when_token_transfer_fails() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_token(Some(accounts.eve)).unwrap();
+            contract.token_balances.insert(accounts.bob, &100);
+            contract.total_token_deposited = 100;
+            set_caller(accounts.bob);
+            mock_token::reject();
+
+            // Act
+            let deposited = contract.deposit_token(50);
+            let withdrawn = contract.withdraw_token(60);
+
+            // Assert
+            assert_eq!(deposited, Err(ContractError::TokenTransferFailed));
+            assert_eq!(withdrawn, Err(ContractError::TokenTransferFailed));
+            assert_eq!(contract.token_balance_of(accounts.bob), 100);
+            assert_eq!(contract.total_token_deposited(), 100);
+            assert!(mock_token::transfers().is_empty());
+        }
+
+        #[ink::test]
+        fn set_token_and_terminate_fail_while_tokens_are_held() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_token(Some(accounts.eve)).unwrap();
+            set_caller(accounts.bob);
+            contract.deposit_token(300).unwrap();
+            set_caller(accounts.alice);
+            contract.pause().unwrap();
+
+            // Act
+            let result = contract.set_token(Some(accounts.frank));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::FundsStillHeld));
+            assert_eq!(contract.token(), Some(accounts.eve));
+            assert_eq!(contract.total_token_deposited(), 300);
+            assert_eq!(contract.terminate(), Err(ContractError::FundsStillHeld));
+            contract.unpause().unwrap();
+            set_caller(accounts.bob);
+            contract.withdraw_token(300).unwrap();
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_token(Some(accounts.frank)), Ok(()));
+        }
+
+        #[ink::test]
+        fn set_token_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.set_token(Some(accounts.eve));
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert_eq!(contract.token(), None);
+        }
+
+        #[ink::test]
+        fn pending_interest_matches_accrue() {
+            // Arrange
+            let (mut contract, accounts) = init_with_interest(10, 1_000);
+            set_block_timestamp(2 * MILLIS_PER_DAY + 5);
+
+            // Act
+            let pending = contract.pending_interest(accounts.bob);
+
+            // Assert
+            assert_eq!(pending, 20);
+            assert_eq!(contract.balance_of(accounts.bob), 10_000);
+            assert_eq!(contract.accrue(accounts.bob), Ok(pending));
+            assert_eq!(contract.pending_interest(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn deposit_and_withdraw_events_carry_new_balance() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(700);
+
+            // Act
+            contract.deposit().unwrap();
+            let deposited = recorded_events().pop();
+            contract.withdraw(Some(200)).unwrap();
+            let withdrawn = recorded_events().pop();
+
+            // Assert
+            assert!(matches!(
+                deposited,
+                Some(Event::Deposited(event)) if event.new_balance == 700
+            ));
+            assert!(matches!(
+                withdrawn,
+                Some(Event::Withdrawn(event)) if event.new_balance == 500
+            ));
+            assert_eq!(contract.balance_of(accounts.bob), 500);
+        }
+
+        #[ink::test]
+        fn withdraw_all_fails_on_zero_valued_entry() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.balances.insert(accounts.bob, &0);
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.withdraw(None);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::AccountWithoutBalance));
+            assert_eq!(
+                contract.withdraw(Some(1)),
+                Err(ContractError::AccountWithoutBalance)
+            );
+        }
+
+        #[ink::test]
+        fn deposit_at_max_deposit_per_tx_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_deposit_per_tx(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(500);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Ok(500));
+        }
+
+        #[ink::test]
+        fn deposit_above_max_deposit_per_tx_fails() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_deposit_per_tx(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(501);
+
+            // Act
+            let result = contract.deposit_for(accounts.charlie);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::DepositTooLarge));
+            assert!(!contract.has_account(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn max_deposit_per_tx_applies_to_quiet_and_split_deposits() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_deposit_per_tx(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(5000);
+
+            // Act
+            let quiet = contract.deposit_quiet();
+            let split =
+                contract.deposit_split(vec![(accounts.charlie, 2500), (accounts.django, 2500)]);
+
+            // Assert
+            assert_eq!(quiet, Err(ContractError::DepositTooLarge));
+            assert_eq!(split, Err(ContractError::DepositTooLarge));
+            assert!(!contract.has_account(accounts.bob));
+            assert!(!contract.has_account(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn deposit_is_unlimited_without_max_deposit_per_tx() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(1_000_000);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Ok(1_000_000));
+        }
+
+        #[ink::test]
+        fn authorized_transfer_works() {
+            // Arrange
+            let (mut contract, accounts, secret_key, signer) = init_with_signer();
+            let signature = sign(
+                &secret_key,
+                &(contract_id(), signer, accounts.bob, 300u128, 0u64),
+            );
+
+            // Act
+            let result = contract.authorized_transfer(signer, accounts.bob, 300, 0, signature);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(signer), 700);
+            assert_eq!(contract.balance_of(accounts.bob), 300);
+            assert_eq!(contract.nonce_of(signer), 1);
+        }
+
+        #[ink::test]
+        fn authorized_transfer_fails_with_signature_for_other_contract() {
+            // Arrange
+            let (mut contract, accounts, secret_key, signer) = init_with_signer();
+            let signature = sign(
+                &secret_key,
+                &(accounts.frank, signer, accounts.bob, 300u128, 0u64),
+            );
+
+            // Act
+            let result = contract.authorized_transfer(signer, accounts.bob, 300, 0, signature);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::BadSignature));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn authorized_transfer_fails_on_replayed_nonce() {
+            // Arrange
+            let (mut contract, accounts, secret_key, signer) = init_with_signer();
+            let signature = sign(
+                &secret_key,
+                &(contract_id(), signer, accounts.bob, 300u128, 0u64),
+            );
+            contract
+                .authorized_transfer(signer, accounts.bob, 300, 0, signature)
+                .unwrap();
+
+            // Act
+            let result = contract.authorized_transfer(signer, accounts.bob, 300, 0, signature);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::InvalidNonce));
+            assert_eq!(contract.balance_of(accounts.bob), 300);
+        }
+
+        #[ink::test]
+        fn authorized_transfer_fails_with_forged_signature() {
+            // Arrange
+            let (mut contract, accounts, _, signer) = init_with_signer();
+            let other_key = secp256k1::SecretKey::from_slice(&[0x2b; 32]).unwrap();
+            let signature = sign(
+                &other_key,
+                &(contract_id(), signer, accounts.eve, 1000u128, 0u64),
+            );
+
+            // Act
+            let result = contract.authorized_transfer(signer, accounts.eve, 1000, 0, signature);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::BadSignature));
+            assert_eq!(contract.balance_of(signer), 1000);
+            assert_eq!(contract.nonce_of(signer), 0);
+        }
+
+        #[ink::test]
+        fn savings_stream_and_escrow_payouts_count_toward_outflow_window() {
+            // Arrange
+            let (mut contract, accounts) = init_with_stream(300, 1_000);
+            contract.set_outflow_window_limit(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(200);
+            contract.deposit_savings().unwrap();
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.django, 1_000)
+                .unwrap();
+            set_block_timestamp(1_000);
+
+            // Act
+            contract.claim_stream().unwrap();
+            contract.withdraw_savings(200).unwrap();
+            let paused_before_escrow = contract.is_paused();
+            contract.settle(id, Some(1)).unwrap();
+
+            // Assert
+            assert!(!paused_before_escrow);
+            assert!(contract.is_paused());
+            assert!(matches!(
+                recorded_events()
+                    .into_iter()
+                    .rev()
+                    .find(|event| matches!(event, Event::AutoPaused(_))),
+                Some(Event::AutoPaused(event)) if event.window_outflow == 501
+            ));
+        }
+
+        #[ink::test]
+        fn stream_and_escrow_payouts_emit_remaining_amount() {
+            // Arrange
+            let (mut contract, accounts) = init_with_stream(300, 1_000);
+            set_caller(accounts.bob);
+            contract.balances.insert(accounts.bob, &5000);
+            set_transferred_value(200);
+            let id = contract
+                .open_escrow(accounts.charlie, accounts.django, 1_000)
+                .unwrap();
+            set_block_timestamp(500);
+
+            // Act
+            contract.claim_stream().unwrap();
+            let stream_event = recorded_events().pop();
+            contract.settle(id, Some(50)).unwrap();
+            let escrow_event = recorded_events().pop();
+
+            // Assert
+            assert!(matches!(
+                stream_event,
+                Some(Event::Withdrawn(event)) if event.balance == 150 && event.new_balance == 150
+            ));
+            assert!(matches!(
+                escrow_event,
+                Some(Event::Withdrawn(event)) if event.balance == 50 && event.new_balance == 150
+            ));
+        }
+
+        #[ink::test]
+        fn withdraw_under_outflow_window_limit_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_outflow_window_limit(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+
+            // Act
+            contract.withdraw(Some(300)).unwrap();
+            let result = contract.withdraw(Some(200));
+
+            // Assert
+            assert_eq!(result, Ok(500));
+            assert!(!contract.is_paused());
+        }
+
+        #[ink::test]
+        fn withdraw_over_outflow_window_limit_auto_pauses() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_outflow_window_limit(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+            contract.withdraw(Some(300)).unwrap();
+
+            // Act
+            let result = contract.withdraw(Some(300));
+
+            // Assert
+            assert_eq!(result, Ok(400));
+            assert!(contract.is_paused());
+            assert!(matches!(
+                recorded_events().pop(),
+                Some(Event::AutoPaused(event)) if event.window_outflow == 600
+            ));
+            assert_eq!(
+                contract.withdraw(Some(100)),
+                Err(ContractError::ContractPaused)
+            );
+            set_caller(accounts.alice);
+            contract.unpause().unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(contract.withdraw(Some(100)), Ok(300));
+            assert!(!contract.is_paused());
+        }
+
+        #[ink::test]
+        fn deposit_with_memo_emits_memo() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(250);
+            let memo = [0x5a; 32];
+
+            // Act
+            let result = contract.deposit_with_memo(memo);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 250);
+            match recorded_events().pop() {
+                Some(Event::DepositedWithMemo(event)) => {
+                    assert_eq!(event.from, accounts.bob);
+                    assert_eq!(event.balance, 250);
+                    assert_eq!(event.memo, memo);
+                }
+                _ => panic!("expected a DepositedWithMemo event"),
+            }
+        }
+
+        #[ink::test]
+        fn can_withdraw_works() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+
+            // Act
+            let result = contract.can_withdraw(1000);
+
+            // Assert
+            assert_eq!(result, Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn can_withdraw_reports_balance_errors() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let without_balance = contract.can_withdraw(1);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+
+            // Assert
+            assert_eq!(without_balance, Err(ContractError::AccountWithoutBalance));
+            assert_eq!(contract.can_withdraw(0), Err(ContractError::ZeroWithdrawal));
+            assert_eq!(
+                contract.can_withdraw(1001),
+                Err(ContractError::ExpectedWithdrawalAmountExceedsAccountBalance)
+            );
+        }
+
+        #[ink::test]
+        fn can_withdraw_reports_pause_and_freeze() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+            set_caller(accounts.alice);
+            contract.freeze(accounts.bob).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let frozen = contract.can_withdraw(100);
+            set_caller(accounts.alice);
+            contract.pause().unwrap();
+            set_caller(accounts.bob);
+
+            // Assert
+            assert_eq!(frozen, Err(ContractError::AccountFrozen));
+            assert_eq!(
+                contract.can_withdraw(100),
+                Err(ContractError::ContractPaused)
+            );
+        }
+
+        #[ink::test]
+        fn can_withdraw_reports_limits_and_cooldown() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_daily_limit(500).unwrap();
+            contract.set_withdraw_cooldown(60_000).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+
+            // Act
+            let over_limit = contract.can_withdraw(600);
+            contract.withdraw(Some(100)).unwrap();
+
+            // Assert
+            assert_eq!(over_limit, Err(ContractError::DailyLimitExceeded));
+            assert_eq!(
+                contract.can_withdraw(100),
+                Err(ContractError::WithdrawCooldownActive)
+            );
+        }
+
+        #[ink::test]
+        fn can_withdraw_reports_lock() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit_locked(MILLIS_PER_DAY).unwrap();
+
+            // Act
+            let result = contract.can_withdraw(100);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::FundsLocked));
+        }
+
+        fn init_with_promo(
+            bonus: Balance,
+            promo_pool: Balance,
+        ) -> (
+            Workshop,
+            ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>,
+        ) {
+            let (mut contract, accounts) = init();
+            contract.set_first_deposit_bonus(bonus).unwrap();
+            if promo_pool > 0 {
+                set_transferred_value(promo_pool);
+                contract.fund_promo_pool().unwrap();
+            }
+            set_caller(accounts.bob);
+            (contract, accounts)
+        }
+
+        #[ink::test]
+        fn first_deposit_gets_bonus() {
+            // Arrange
+            let (mut contract, accounts) = init_with_promo(50, 80);
+            set_transferred_value(100);
+
+            // Act
+            let first = contract.deposit();
+            let second = contract.deposit();
+
+            // Assert
+            assert_eq!(first, Ok(150));
+            assert_eq!(second, Ok(250));
+            assert_eq!(contract.promo_pool(), 30);
+            assert_eq!(contract.lifetime_deposited_of(accounts.bob), 200);
+        }
+
+        #[ink::test]
+        fn first_deposit_skips_bonus_with_empty_pool() {
+            // Arrange
+            let (mut contract, accounts) = init_with_promo(50, 0);
+            set_transferred_value(100);
+
+            // Act
+            let result = contract.deposit();
+
+            // Assert
+            assert_eq!(result, Ok(100));
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert!(!recorded_events()
+                .iter()
+                .any(|event| matches!(event, Event::BonusCredited(_))));
+        }
+
+        fn init_with_pending_withdrawal(
+            delay: Timestamp,
+        ) -> (
+            Workshop,
+            ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>,
+        ) {
+            let (mut contract, accounts) = init();
+            contract.set_withdrawal_delay(delay).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+            contract.request_withdrawal(400).unwrap();
+            (contract, accounts)
+        }
+
+        #[ink::test]
+        fn request_withdrawal_debits_balance() {
+            // Arrange
+            let (contract, accounts) = init_with_pending_withdrawal(60_000);
+
+            // Assert
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+            assert_eq!(contract.pending_withdrawal_of(accounts.bob), Some((400, 0)));
+            assert_eq!(contract.total_deposited(), 1000);
+        }
+
+        #[ink::test]
+        fn execute_withdrawal_works_after_delay() {
+            // Arrange
+            let (mut contract, accounts) = init_with_pending_withdrawal(60_000);
+            let chain_balance = get_chain_balance(accounts.bob);
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.execute_withdrawal(accounts.bob),
+                Err(ContractError::WithdrawalDelayActive)
+            );
+            set_block_timestamp(60_000);
+
+            // Act
+            let result = contract.execute_withdrawal(accounts.bob);
+
+            // Assert
+            assert_eq!(result, Ok(400));
+            assert_eq!(get_chain_balance(accounts.bob), chain_balance + 400);
+            assert_eq!(contract.pending_withdrawal_of(accounts.bob), None);
+            assert_eq!(contract.total_deposited(), 600);
+            assert_eq!(
+                contract.execute_withdrawal(accounts.bob),
+                Err(ContractError::NoPendingWithdrawal)
+            );
+        }
+
+        #[ink::test]
+        fn cancel_withdrawal_recredits_balance() {
+            // Arrange
+            let (mut contract, accounts) = init_with_pending_withdrawal(60_000);
+
+            // Act
+            let result = contract.cancel_withdrawal();
+
+            // Assert
+            assert_eq!(result, Ok(1000));
+            assert_eq!(contract.balance_of(accounts.bob), 1000);
+            assert_eq!(contract.pending_withdrawal_of(accounts.bob), None);
+            set_block_timestamp(60_000);
+            assert_eq!(
+                contract.execute_withdrawal(accounts.bob),
+                Err(ContractError::NoPendingWithdrawal)
+            );
+        }
+
+        #[ink::test]
+        fn cancel_withdrawal_restores_daily_limit_and_preauthorization() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_daily_limit(100).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+            contract.preauthorize(150).unwrap();
+            contract.request_withdrawal(100).unwrap();
+
+            // Act
+            contract.cancel_withdrawal().unwrap();
+
+            // Assert
+            assert_eq!(contract.remaining_daily_limit(), 100);
+            assert_eq!(contract.preauthorization_of(accounts.bob), Some(150));
+            assert_eq!(contract.withdraw(Some(10)), Ok(990));
+        }
+
+        #[ink::test]
+        fn savings_escrow_and_forfeit_count_toward_block_op_limit() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_ops_per_block(1).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+            set_transferred_value(100);
+
+            // Act
+            let savings = contract.deposit_savings();
+            let escrow = contract.open_escrow(accounts.charlie, accounts.django, 1_000);
+            let forfeit = contract.forfeit(Some(10));
+            advance_blocks(1);
+            let next_block = contract.open_escrow(accounts.charlie, accounts.django, 1_000);
+
+            // Assert
+            assert_eq!(savings, Ok(100));
+            assert_eq!(escrow, Err(ContractError::RateLimited));
+            assert_eq!(forfeit, Err(ContractError::RateLimited));
+            assert_eq!(next_block, Ok(0));
+        }
+
+        #[ink::test]
+        fn queued_withdrawal_counts_toward_block_op_limit() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+            set_caller(accounts.alice);
+            contract.set_max_ops_per_block(1).unwrap();
+            set_caller(accounts.bob);
+            advance_blocks(1);
+
+            // Act
+            let requested = contract.request_withdrawal(100);
+            let same_block = contract.execute_withdrawal(accounts.bob);
+            advance_blocks(1);
+            let next_block = contract.execute_withdrawal(accounts.bob);
+
+            // Assert
+            assert_eq!(requested, Ok(()));
+            assert_eq!(same_block, Err(ContractError::RateLimited));
+            assert_eq!(next_block, Ok(100));
+        }
+
+        #[ink::test]
+        fn remaining_daily_limit_drops_after_withdrawal() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_daily_limit(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+
+            // Act
+            contract.withdraw(Some(200)).unwrap();
+
+            // Assert
+            assert_eq!(contract.remaining_daily_limit(), 300);
+        }
+
+        #[ink::test]
+        fn remaining_daily_limit_resets_on_new_day() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_daily_limit(500).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+            contract.withdraw(Some(500)).unwrap();
+
+            // Act
+            set_block_timestamp(MILLIS_PER_DAY);
+
+            // Assert
+            assert_eq!(contract.remaining_daily_limit(), 500);
+        }
+
+        #[ink::test]
+        fn remaining_daily_limit_is_unbounded_without_limit() {
+            // Arrange
+            let (contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Assert
+            assert_eq!(contract.remaining_daily_limit(), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn forfeit_credits_penalty_recipient() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_penalty_recipient(accounts.frank).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.forfeit(Some(300));
+
+            // Assert
+            assert_eq!(result, Ok(300));
+            assert_eq!(contract.balance_of(accounts.frank), 300);
+            assert_eq!(contract.balance_of(accounts.alice), 0);
+            assert_eq!(contract.treasury(), 300);
+            assert_eq!(contract.config().penalty_recipient, accounts.frank);
+        }
+
+        #[ink::test]
+        fn set_penalty_recipient_fails_for_non_owner() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+
+            // Act
+            let result = contract.set_penalty_recipient(accounts.bob);
+
+            // Assert
+            assert_eq!(result, Err(ContractError::NotOwner));
+            assert_eq!(contract.penalty_recipient(), accounts.alice);
+        }
+
+        #[ink::test]
+        fn last_activity_block_records_deposit_and_transfer() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            advance_blocks(3);
+            set_transferred_value(500);
+            contract.deposit().unwrap();
+            advance_blocks(2);
+
+            // Act
+            let after_deposit = contract.last_activity_block_of(accounts.bob);
+            contract.transfer(accounts.charlie, 100).unwrap();
+
+            // Assert
+            assert_eq!(after_deposit, Some(3));
+            assert_eq!(contract.last_activity_block_of(accounts.bob), Some(5));
+            assert_eq!(contract.last_activity_block_of(accounts.charlie), Some(5));
+            assert_eq!(contract.last_activity_block_of(accounts.django), None);
+        }
+
+        #[ink::test]
+        fn last_activity_block_records_queued_withdrawal() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_withdrawal_delay(60_000).unwrap();
+            set_caller(accounts.bob);
+            set_transferred_value(1000);
+            contract.deposit().unwrap();
+            advance_blocks(2);
+
+            // Act
+            contract.request_withdrawal(400).unwrap();
+            let after_request = contract.last_activity_block_of(accounts.bob);
+            advance_blocks(3);
+            set_block_timestamp(10 * MILLIS_PER_DAY);
+            set_caller(accounts.charlie);
+            contract.execute_withdrawal(accounts.bob).unwrap();
+
+            // Assert
+            assert_eq!(after_request, Some(2));
+            assert_eq!(contract.last_activity_block_of(accounts.bob), Some(5));
+            assert_eq!(contract.last_activity_block_of(accounts.charlie), None);
+        }
+
+        #[ink::test]
+        fn new_from_config_round_trips_config() {
+            // Arrange
+            let accounts = get_default_accounts();
+            let config = Config {
+                owner: accounts.bob,
+                paused: true,
+                fee_bps: 150,
+                min_deposit: 10,
+                max_balance: 5000,
+                daily_limit: 2000,
+                emit_events: false,
+                fee_tiers: vec![(1000, 100), (3000, 50)],
+                max_deposit_per_tx: 1000,
+                penalty_recipient: accounts.frank,
+            };
+
+            // Act
+            let contract = Workshop::new_from_config(config.clone());
+
+            // Assert
+            assert_eq!(contract.config(), config);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "invalid fee schedule")]
+        fn new_from_config_fails_on_invalid_fee() {
+            // Arrange
+            let (contract, _) = init();
+            let config = Config {
+                fee_bps: MAX_BPS + 1,
+                ..contract.config()
+            };
+
+            // Act
+            Workshop::new_from_config(config);
+        }
+
+        #[ink::test]
+        fn preview_withdraw_applies_flat_fee() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(250).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let previews: Vec<(Balance, Balance)> = [0, 39, 100, 1000]
+                .into_iter()
+                .map(|amount| contract.preview_withdraw(amount).unwrap())
+                .collect();
+
+            // Assert
+            assert_eq!(previews, vec![(0, 0), (39, 0), (98, 2), (975, 25)]);
+        }
+
+        #[ink::test]
+        fn preview_withdraw_matches_tiered_withdraw() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(50).unwrap();
+            contract
+                .set_fee_tiers(vec![(100, 100), (1000, 200), (5000, 300)])
+                .unwrap();
+            contract.balances.insert(accounts.bob, &20_000);
+            contract.total_deposited = 20_000;
+            set_caller(accounts.bob);
+
+            for amount in [50, 500, 4999, 6000] {
+                // Act
+                let (net_amount, fee) = contract.preview_withdraw(amount).unwrap();
+                let chain_before = get_chain_balance(accounts.bob);
+                let treasury_before = contract.balance_of(accounts.alice);
+                contract.withdraw(Some(amount)).unwrap();
+
+                // Assert
+                assert_eq!(net_amount + fee, amount);
+                assert_eq!(get_chain_balance(accounts.bob) - chain_before, net_amount);
+                assert_eq!(contract.balance_of(accounts.alice) - treasury_before, fee);
+            }
+        }
+
+        #[ink::test]
+        fn preview_withdraw_is_free_for_owner() {
+            // Arrange
+            let (mut contract, _) = init();
+            contract.set_fee_bps(500).unwrap();
+
+            // Act
+            let preview = contract.preview_withdraw(1000);
+
+            // Assert
+            assert_eq!(preview, Ok((1000, 0)));
+        }
+
+        #[ink::test]
+        fn preview_withdraw_fails_when_fee_overflows() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(500).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let preview = contract.preview_withdraw(Balance::MAX);
+
+            // Assert
+            assert_eq!(preview, Err(ContractError::BalanceOverflow));
+        }
+
+        #[ink::test]
+        fn ops_over_block_limit_are_rate_limited() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_ops_per_block(2).unwrap();
+            contract.balances.insert(accounts.bob, &1000);
+            contract.total_deposited = 1000;
+            set_caller(accounts.bob);
+
+            // Act
+            let first = contract.transfer(accounts.charlie, 100);
+            let second = contract.withdraw(Some(100));
+            let third = contract.transfer(accounts.charlie, 100);
+            advance_blocks(1);
+            let next_block = contract.transfer(accounts.charlie, 100);
+
+            // Assert
+            assert_eq!(first, Ok(()));
+            assert_eq!(second, Ok(800));
+            assert_eq!(third, Err(ContractError::RateLimited));
+            assert_eq!(next_block, Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 200);
+        }
+
+        #[ink::test]
+        fn account_settings_count_toward_block_op_limit() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_max_ops_per_block(2).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let approved = contract.approve(accounts.charlie, 100);
+            let labelled = contract.set_label([1; 32]);
+            let preauthorized = contract.preauthorize(500);
+
+            // Assert
+            assert_eq!(approved, Ok(()));
+            assert_eq!(labelled, Ok(()));
+            assert_eq!(preauthorized, Err(ContractError::RateLimited));
+            assert_eq!(
+                contract.set_operator(accounts.charlie, true),
+                Err(ContractError::RateLimited)
+            );
+            assert_eq!(
+                contract.accrue(accounts.bob),
+                Err(ContractError::RateLimited)
+            );
+            assert_eq!(contract.preauthorization_of(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn bootstrap_matches_getters() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            contract.set_fee_bps(100).unwrap();
+            contract.balances.insert(accounts.bob, &700);
+            contract.freeze(accounts.bob).unwrap();
+            contract.add_to_whitelist(accounts.bob).unwrap();
+            set_caller(accounts.bob);
+
+            // Act
+            let (config, balance, frozen, whitelisted) = contract.bootstrap();
+
+            // Assert
+            assert_eq!(config, contract.config());
+            assert_eq!(balance, contract.balance_of(accounts.bob));
+            assert_eq!(balance, 700);
+            assert!(frozen);
+            assert_eq!(frozen, contract.is_frozen(accounts.bob));
+            assert!(whitelisted);
+            assert_eq!(whitelisted, contract.is_whitelisted(accounts.bob));
+        }
+
+        #[ink::test]
+        fn set_label_only_labels_caller() {
+            // Arrange
+            let (mut contract, accounts) = init();
+            set_caller(accounts.bob);
+            contract.set_label([1; 32]).unwrap();
+
+            // Act
+            set_caller(accounts.charlie);
+            contract.set_label([2; 32]).unwrap();
+
+            // Assert
+            assert_eq!(contract.label_of(accounts.bob), Some([1; 32]));
+            assert_eq!(contract.label_of(accounts.charlie), Some([2; 32]));
+            assert_eq!(contract.label_of(accounts.django), None);
         }
     }
 }